@@ -0,0 +1,200 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use zip::ZipArchive;
+use zip::ZipWriter;
+use zip::write::FileOptions;
+
+use crate::fs_utils;
+
+enum ArchiveProgress {
+    Step(usize, usize),
+    Finished(io::Result<PathBuf>)
+}
+
+// A zip being created or extracted on a background thread.
+pub struct ArchiveTask {
+    description: String,
+    progress: f32,
+    receiver: Receiver<ArchiveProgress>
+}
+
+impl ArchiveTask {
+    // Compresses `sources` into a new archive inside `dest_dir`.
+    pub fn compress(sources: Vec<PathBuf>, dest_dir: &Path) -> ArchiveTask {
+        let name = {
+            if let [source] = sources.as_slice() {
+                let name = if source.is_dir() { source.file_name() } else { source.file_stem() };
+                name.unwrap_or_default().to_string_lossy().to_string()
+            }
+            else {
+                "Archive".to_string()
+            }
+        };
+
+        let target = fs_utils::unique_path(dest_dir, &dest_dir.join(format!("{}.zip", name)));
+        let description = format!("Compressing to {}", target.file_name().unwrap_or_default().to_string_lossy());
+
+        ArchiveTask::spawn(description, move | progress | {
+            if let Err(e) = compress(&sources, &target, progress) {
+                // Don't leave half an archive behind.
+                let _ = fs_utils::remove_entry(&target);
+                return Err(e);
+            }
+
+            Ok(target)
+        })
+    }
+
+    // Extracts `archive` into a new folder inside `dest_dir` named after it.
+    pub fn extract(archive: &Path, dest_dir: &Path) -> ArchiveTask {
+        let archive = archive.to_path_buf();
+        let name = archive.file_stem().unwrap_or_default().to_string_lossy().to_string();
+
+        let target = fs_utils::unique_path(dest_dir, &dest_dir.join(&name));
+
+        let description = format!("Extracting {}", archive.file_name().unwrap_or_default().to_string_lossy());
+
+        ArchiveTask::spawn(description, move | progress | {
+            if let Err(e) = extract(&archive, &target, progress) {
+                // Nor a partly extracted folder.
+                let _ = fs_utils::remove_entry(&target);
+                return Err(e);
+            }
+
+            Ok(target)
+        })
+    }
+
+    fn spawn<F>(description: String, task: F) -> ArchiveTask
+    where
+        F: FnOnce(&dyn Fn(usize, usize)) -> io::Result<PathBuf> + Send + 'static
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let progress = | done, total | {
+                let _ = sender.send(ArchiveProgress::Step(done, total));
+            };
+
+            let result = task(&progress);
+            let _ = sender.send(ArchiveProgress::Finished(result));
+        });
+
+        ArchiveTask {
+            description,
+            progress: 0.0,
+            receiver
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    // Returns the path of the created archive or folder once the task is done.
+    pub fn poll(&mut self) -> Option<io::Result<PathBuf>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(ArchiveProgress::Step(done, total)) => self.progress = done as f32 / total.max(1) as f32,
+                Ok(ArchiveProgress::Finished(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => return Some(Err(io::Error::other("The archive task stopped unexpectedly")))
+            }
+        }
+    }
+}
+
+fn zip_error(error: zip::result::ZipError) -> io::Error {
+    io::Error::other(error)
+}
+
+fn compress(sources: &[PathBuf], target: &Path, progress: &dyn Fn(usize, usize)) -> io::Result<()> {
+    // Every entry with its name inside the archive, relative to the folder the sources live in.
+    let mut entries = Vec::new();
+
+    for source in sources {
+        let base = source.parent().unwrap_or(source);
+        collect_entries(source, base, &mut entries)?;
+    }
+
+    let mut writer = ZipWriter::new(File::create(target)?);
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for (done, (path, name)) in entries.iter().enumerate() {
+        if path.is_dir() {
+            writer.add_directory(name.as_str(), options).map_err(zip_error)?;
+        }
+        else {
+            writer.start_file(name.as_str(), options).map_err(zip_error)?;
+            io::copy(&mut File::open(path)?, &mut writer)?;
+        }
+
+        progress(done + 1, entries.len());
+    }
+
+    writer.finish().map_err(zip_error)?;
+
+    Ok(())
+}
+
+fn collect_entries(path: &Path, base: &Path, entries: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+    let name = path.strip_prefix(base).unwrap_or(path)
+        .components()
+        .map(| component | component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+    ;
+
+    // Symlinks aren't followed, a link to a parent folder would never end.
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        entries.push((path.to_path_buf(), format!("{}/", name)));
+
+        for entry in std::fs::read_dir(path)? {
+            collect_entries(&entry?.path(), base, entries)?;
+        }
+    }
+    else if path.is_file() {
+        entries.push((path.to_path_buf(), name));
+    }
+
+    Ok(())
+}
+
+fn extract(archive: &Path, target: &Path, progress: &dyn Fn(usize, usize)) -> io::Result<()> {
+    let mut archive = ZipArchive::new(File::open(archive)?).map_err(zip_error)?;
+    let total = archive.len();
+
+    std::fs::create_dir_all(target)?;
+
+    for idx in 0..total {
+        let mut file = archive.by_index(idx).map_err(zip_error)?;
+
+        // Skip anything that would end up outside the target folder.
+        let output = match file.enclosed_name() {
+            Some(name) => target.join(name),
+            None => continue
+        };
+
+        if file.is_dir() {
+            std::fs::create_dir_all(&output)?;
+        }
+        else {
+            if let Some(parent) = output.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            io::copy(&mut file, &mut File::create(&output)?)?;
+        }
+
+        progress(idx + 1, total);
+    }
+
+    Ok(())
+}
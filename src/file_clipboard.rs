@@ -0,0 +1,238 @@
+use std::io;
+use std::path::PathBuf;
+
+use crate::ClipboardMode;
+
+// The system clipboard, as far as copied files go. Lets entries be copied here and pasted
+// in the platform's file manager, and the other way around.
+pub trait FileClipboard {
+    fn set(&self, paths: &[PathBuf], mode: ClipboardMode) -> io::Result<()>;
+    // None if the clipboard doesn't hold any files.
+    fn get(&self) -> Option<(Vec<PathBuf>, ClipboardMode)>;
+}
+
+#[cfg(windows)]
+pub fn system() -> Box<dyn FileClipboard> {
+    Box::new(windows::ShellClipboard)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn system() -> Box<dyn FileClipboard> {
+    Box::new(gnome::GnomeClipboard)
+}
+
+// Copied entries only live in the app's own clipboard here.
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+pub fn system() -> Box<dyn FileClipboard> {
+    Box::new(Unsupported)
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+struct Unsupported;
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+impl FileClipboard for Unsupported {
+    fn set(&self, _paths: &[PathBuf], _mode: ClipboardMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn get(&self) -> Option<(Vec<PathBuf>, ClipboardMode)> {
+        None
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::io;
+    use std::path::PathBuf;
+
+    use clipboard_win::{formats, Clipboard, Getter, Setter};
+
+    use super::FileClipboard;
+    use crate::ClipboardMode;
+
+    // Explorer tells cut files apart from copied ones through this format.
+    const DROP_EFFECT_FORMAT: &str = "Preferred DropEffect";
+    const DROPEFFECT_MOVE: u32 = 2;
+
+    // The shell's CF_HDROP file list, what Explorer uses for copying and pasting files.
+    pub struct ShellClipboard;
+
+    impl FileClipboard for ShellClipboard {
+        fn set(&self, paths: &[PathBuf], mode: ClipboardMode) -> io::Result<()> {
+            let paths: Vec<String> = paths.iter().map(| path | path.to_string_lossy().to_string()).collect();
+
+            let _clipboard = Clipboard::new_attempts(10).map_err(to_io_error)?;
+            clipboard_win::raw::empty().map_err(to_io_error)?;
+            formats::FileList.write_clipboard(paths.as_slice()).map_err(to_io_error)?;
+
+            if mode == ClipboardMode::Cut {
+                if let Some(format) = clipboard_win::register_format(DROP_EFFECT_FORMAT) {
+                    formats::RawData(format.get()).write_clipboard(&DROPEFFECT_MOVE.to_le_bytes()[..]).map_err(to_io_error)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        fn get(&self) -> Option<(Vec<PathBuf>, ClipboardMode)> {
+            let _clipboard = Clipboard::new_attempts(10).ok()?;
+
+            let mut paths: Vec<String> = Vec::new();
+            formats::FileList.read_clipboard(&mut paths).ok()?;
+
+            let mut drop_effect = Vec::new();
+            let is_cut = clipboard_win::register_format(DROP_EFFECT_FORMAT)
+                .and_then(| format | formats::RawData(format.get()).read_clipboard(&mut drop_effect).ok())
+                .and_then(| _ | drop_effect.get(..4).map(| bytes | u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])))
+                .map(| effect | effect & DROPEFFECT_MOVE != 0)
+                .unwrap_or(false)
+            ;
+
+            let mode = if is_cut { ClipboardMode::Cut } else { ClipboardMode::Copy };
+            Some((paths.into_iter().map(PathBuf::from).collect(), mode))
+        }
+    }
+
+    fn to_io_error(error: clipboard_win::ErrorCode) -> io::Error {
+        io::Error::other(error.to_string())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod gnome {
+    use std::ffi::OsString;
+    use std::io::{self, Write};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+
+    use super::FileClipboard;
+    use crate::ClipboardMode;
+
+    // What GTK file managers (Nautilus, Nemo, Thunar…) put on the clipboard: "copy" or "cut",
+    // then a file:// URI per line.
+    const FORMAT: &str = "x-special/gnome-copied-files";
+
+    // There's no clipboard access without a window of our own, so this goes through
+    // wl-clipboard on Wayland and xclip on X11.
+    pub struct GnomeClipboard;
+
+    impl GnomeClipboard {
+        fn is_wayland() -> bool {
+            std::env::var_os("WAYLAND_DISPLAY").is_some()
+        }
+    }
+
+    impl FileClipboard for GnomeClipboard {
+        fn set(&self, paths: &[PathBuf], mode: ClipboardMode) -> io::Result<()> {
+            let mut contents = if mode == ClipboardMode::Cut { "cut" } else { "copy" }.to_string();
+
+            for path in paths {
+                contents.push('\n');
+                contents.push_str(&path_to_uri(path));
+            }
+
+            let mut command = {
+                if GnomeClipboard::is_wayland() {
+                    let mut command = Command::new("wl-copy");
+                    command.args(["--type", FORMAT]);
+                    command
+                }
+                else {
+                    let mut command = Command::new("xclip");
+                    command.args(["-selection", "clipboard", "-target", FORMAT, "-in"]);
+                    command
+                }
+            };
+
+            let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(contents.as_bytes())?;
+            }
+
+            // Both fork into the background to keep serving the clipboard, this only waits for the parent.
+            let status = child.wait()?;
+
+            if status.success() {
+                Ok(())
+            }
+            else {
+                Err(io::Error::other(format!("Couldn't set the clipboard ({})", status)))
+            }
+        }
+
+        fn get(&self) -> Option<(Vec<PathBuf>, ClipboardMode)> {
+            let mut command = {
+                if GnomeClipboard::is_wayland() {
+                    let mut command = Command::new("wl-paste");
+                    command.args(["--no-newline", "--type", FORMAT]);
+                    command
+                }
+                else {
+                    let mut command = Command::new("xclip");
+                    command.args(["-selection", "clipboard", "-target", FORMAT, "-out"]);
+                    command
+                }
+            };
+
+            let output = command.stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+
+            if !output.status.success() {
+                return None;
+            }
+
+            let contents = String::from_utf8_lossy(&output.stdout);
+            let mut lines = contents.lines();
+
+            let mode = match lines.next()?.trim() {
+                "copy" => ClipboardMode::Copy,
+                "cut" => ClipboardMode::Cut,
+                _ => return None
+            };
+
+            let paths: Vec<PathBuf> = lines.filter_map(uri_to_path).collect();
+
+            if paths.is_empty() {
+                None
+            }
+            else {
+                Some((paths, mode))
+            }
+        }
+    }
+
+    fn path_to_uri(path: &Path) -> String {
+        let mut uri = "file://".to_string();
+
+        for byte in path.as_os_str().as_bytes() {
+            match *byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(*byte as char),
+                byte => uri.push_str(&format!("%{:02X}", byte))
+            }
+        }
+
+        uri
+    }
+
+    fn uri_to_path(uri: &str) -> Option<PathBuf> {
+        let encoded = uri.trim().strip_prefix("file://")?.as_bytes();
+        let mut bytes = Vec::with_capacity(encoded.len());
+        let mut idx = 0;
+
+        while idx < encoded.len() {
+            if encoded[idx] == b'%' {
+                let hex = std::str::from_utf8(encoded.get(idx + 1..idx + 3)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                idx += 3;
+            }
+            else {
+                bytes.push(encoded[idx]);
+                idx += 1;
+            }
+        }
+
+        Some(PathBuf::from(OsString::from_vec(bytes)))
+    }
+}
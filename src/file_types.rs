@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Category {
+    Images,
+    Documents,
+    Audio,
+    Video,
+    Archives,
+    Code
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Images,
+        Category::Documents,
+        Category::Audio,
+        Category::Video,
+        Category::Archives,
+        Category::Code
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Images => "Images",
+            Category::Documents => "Documents",
+            Category::Audio => "Audio",
+            Category::Video => "Video",
+            Category::Archives => "Archives",
+            Category::Code => "Code"
+        }
+    }
+}
+
+// A quick filter defined by the user, like "Photos" for jpg, png and heic files.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SavedFilter {
+    pub name: String,
+    // Extensions and media type prefixes separated by commas or spaces, as typed in the settings.
+    // Anything with a slash in it is a media type prefix, like "image/".
+    pub patterns: String,
+
+    #[serde(skip)]
+    pub active: bool
+}
+
+impl SavedFilter {
+    pub fn matches(&self, extension: &str, media_type: Option<&str>) -> bool {
+        self.patterns.split(| c: char | c == ',' || c.is_whitespace())
+            .filter(| pattern | !pattern.is_empty())
+            .any(| pattern | {
+                if pattern.contains('/') {
+                    media_type.map(| media_type | media_type.starts_with(pattern)).unwrap_or(false)
+                }
+                else {
+                    pattern.trim_start_matches('.').eq_ignore_ascii_case(extension)
+                }
+            })
+    }
+}
+
+// Readable names for file types, shown in the Type column and the properties dialog.
+// Extensions are checked first since they're usually more specific than the detected media type,
+// then the media type, then the extension itself. The raw media type is the last resort.
+pub fn type_name(extension: &str, media_type: Option<&str>) -> String {
+    type_info(extension, media_type).0
+}
+
+// The readable name along with the category used by the quick filters, so both always agree.
+pub fn type_info(extension: &str, media_type: Option<&str>) -> (String, Option<Category>) {
+    let extension = extension.to_lowercase();
+
+    if let Some((name, category)) = name_for_extension(&extension).or_else(|| media_type.and_then(name_for_media_type)) {
+        return (name.to_string(), category);
+    }
+
+    // Types we don't know by name can still be told apart by the media type's family.
+    let category = media_type.and_then(| media_type | {
+        match media_type.split('/').next() {
+            Some("image") => Some(Category::Images),
+            Some("audio") => Some(Category::Audio),
+            Some("video") => Some(Category::Video),
+            Some("text") => Some(Category::Documents),
+            _ => None
+        }
+    });
+
+    if !extension.is_empty() {
+        return (format!("{} File", extension.to_uppercase()), category);
+    }
+
+    match media_type {
+        // Anything with no extension that couldn't be identified is detected as this.
+        Some("application/octet-stream") | None => ("File".to_string(), category),
+        Some(media_type) => (media_type.to_string(), category)
+    }
+}
+
+pub fn is_known_extension(extension: &str) -> bool {
+    name_for_extension(&extension.to_lowercase()).is_some()
+}
+
+fn name_for_extension(extension: &str) -> Option<(&'static str, Option<Category>)> {
+    let name = match extension {
+        "txt" => ("Text Document", Some(Category::Documents)),
+        "md" => ("Markdown Document", Some(Category::Documents)),
+        "pdf" => ("PDF Document", Some(Category::Documents)),
+        "doc" | "docx" => ("Word Document", Some(Category::Documents)),
+        "xls" | "xlsx" => ("Excel Spreadsheet", Some(Category::Documents)),
+        "ppt" | "pptx" => ("PowerPoint Presentation", Some(Category::Documents)),
+        "odt" => ("OpenDocument Text", Some(Category::Documents)),
+        "ods" => ("OpenDocument Spreadsheet", Some(Category::Documents)),
+        "odp" => ("OpenDocument Presentation", Some(Category::Documents)),
+        "csv" => ("CSV File", Some(Category::Documents)),
+
+        "png" => ("PNG Image", Some(Category::Images)),
+        "jpg" | "jpeg" => ("JPEG Image", Some(Category::Images)),
+        "gif" => ("GIF Image", Some(Category::Images)),
+        "webp" => ("WebP Image", Some(Category::Images)),
+        "bmp" => ("Bitmap Image", Some(Category::Images)),
+        "svg" => ("SVG Image", Some(Category::Images)),
+        "ico" => ("Icon", Some(Category::Images)),
+
+        "mp3" => ("MP3 Audio", Some(Category::Audio)),
+        "flac" => ("FLAC Audio", Some(Category::Audio)),
+        "ogg" => ("Ogg Audio", Some(Category::Audio)),
+        "wav" => ("WAV Audio", Some(Category::Audio)),
+        "mp4" => ("MP4 Video", Some(Category::Video)),
+        "mkv" => ("Matroska Video", Some(Category::Video)),
+        "webm" => ("WebM Video", Some(Category::Video)),
+        "avi" => ("AVI Video", Some(Category::Video)),
+
+        "zip" => ("ZIP Archive", Some(Category::Archives)),
+        "tar" => ("TAR Archive", Some(Category::Archives)),
+        "gz" => ("Gzip Archive", Some(Category::Archives)),
+        "xz" => ("XZ Archive", Some(Category::Archives)),
+        "bz2" => ("Bzip2 Archive", Some(Category::Archives)),
+        "7z" => ("7-Zip Archive", Some(Category::Archives)),
+        "rar" => ("RAR Archive", Some(Category::Archives)),
+
+        "rs" => ("Rust Source", Some(Category::Code)),
+        "c" => ("C Source", Some(Category::Code)),
+        "h" => ("C Header", Some(Category::Code)),
+        "cpp" | "cc" | "cxx" => ("C++ Source", Some(Category::Code)),
+        "hpp" => ("C++ Header", Some(Category::Code)),
+        "py" => ("Python Script", Some(Category::Code)),
+        "js" => ("JavaScript Source", Some(Category::Code)),
+        "ts" => ("TypeScript Source", Some(Category::Code)),
+        "java" => ("Java Source", Some(Category::Code)),
+        "go" => ("Go Source", Some(Category::Code)),
+        "sh" => ("Shell Script", Some(Category::Code)),
+        "html" | "htm" => ("HTML Document", Some(Category::Code)),
+        "css" => ("CSS Stylesheet", Some(Category::Code)),
+        "json" => ("JSON File", Some(Category::Code)),
+        "toml" => ("TOML File", Some(Category::Code)),
+        "yaml" | "yml" => ("YAML File", Some(Category::Code)),
+        "xml" => ("XML Document", Some(Category::Code)),
+
+        "exe" => ("Application", None),
+        "dll" => ("Application Extension", None),
+        "so" => ("Shared Library", None),
+        "iso" => ("Disc Image", None),
+        "ttf" | "otf" => ("Font", None),
+        _ => return None
+    };
+
+    Some(name)
+}
+
+fn name_for_media_type(media_type: &str) -> Option<(&'static str, Option<Category>)> {
+    let name = match media_type {
+        "application/pdf" => ("PDF Document", Some(Category::Documents)),
+        "application/msword" | "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => ("Word Document", Some(Category::Documents)),
+        "application/vnd.ms-excel" | "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => ("Excel Spreadsheet", Some(Category::Documents)),
+        "application/vnd.ms-powerpoint" | "application/vnd.openxmlformats-officedocument.presentationml.presentation" => ("PowerPoint Presentation", Some(Category::Documents)),
+        "application/zip" => ("ZIP Archive", Some(Category::Archives)),
+        "application/gzip" => ("Gzip Archive", Some(Category::Archives)),
+        "application/x-tar" => ("TAR Archive", Some(Category::Archives)),
+        "application/x-7z-compressed" => ("7-Zip Archive", Some(Category::Archives)),
+        "application/vnd.rar" | "application/x-rar-compressed" => ("RAR Archive", Some(Category::Archives)),
+        "application/x-executable" | "application/x-elf" => ("Executable", None),
+        "application/vnd.microsoft.portable-executable" => ("Application", None),
+        "image/png" => ("PNG Image", Some(Category::Images)),
+        "image/jpeg" => ("JPEG Image", Some(Category::Images)),
+        "image/gif" => ("GIF Image", Some(Category::Images)),
+        "image/webp" => ("WebP Image", Some(Category::Images)),
+        "audio/mpeg" => ("MP3 Audio", Some(Category::Audio)),
+        "video/mp4" => ("MP4 Video", Some(Category::Video)),
+        "text/plain" => ("Text Document", Some(Category::Documents)),
+        _ => return None
+    };
+
+    Some(name)
+}
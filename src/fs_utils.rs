@@ -0,0 +1,402 @@
+use std::io;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+// Finds a free name for `source` inside `dir`, appending " (copy)", " (copy 2)", etc.
+// before the extension if an entry with the same name is already there.
+pub fn unique_path(dir: &Path, source: &Path) -> PathBuf {
+    let file_name = source.file_name().unwrap_or_default();
+    let target = dir.join(file_name);
+
+    if !target.exists() {
+        return target;
+    }
+
+    let (stem, extension) = {
+        if source.is_dir() {
+            (file_name.to_os_string(), None)
+        }
+        else {
+            let stem = source.file_stem().unwrap_or(file_name).to_os_string();
+            (stem, source.extension().map(| e | e.to_os_string()))
+        }
+    };
+
+    let mut attempt = 1;
+
+    loop {
+        let mut name = OsString::from(&stem);
+
+        if attempt == 1 {
+            name.push(" (copy)");
+        }
+        else {
+            name.push(format!(" (copy {})", attempt));
+        }
+
+        if let Some(extension) = extension.as_ref() {
+            name.push(".");
+            name.push(extension);
+        }
+
+        let target = dir.join(name);
+
+        if !target.exists() {
+            return target;
+        }
+
+        attempt += 1;
+    }
+}
+
+// Finds a free name for a link to `target` inside `dir`, "name (link).ext", "name (link 2).ext", etc.
+// The extension stays last so the link still opens with the right application.
+pub fn link_path(dir: &Path, target: &Path) -> PathBuf {
+    let file_name = target.file_name().unwrap_or(target.as_os_str());
+
+    let (stem, extension) = {
+        if target.is_dir() {
+            (file_name.to_os_string(), None)
+        }
+        else {
+            (target.file_stem().unwrap_or(file_name).to_os_string(), target.extension().map(| e | e.to_os_string()))
+        }
+    };
+
+    let mut attempt = 1;
+
+    loop {
+        let mut name = OsString::from(&stem);
+
+        if attempt == 1 {
+            name.push(" (link)");
+        }
+        else {
+            name.push(format!(" (link {})", attempt));
+        }
+
+        if let Some(extension) = extension.as_ref() {
+            name.push(".");
+            name.push(extension);
+        }
+
+        let link = dir.join(name);
+
+        // symlink_metadata so broken links count as taken too.
+        if std::fs::symlink_metadata(&link).is_err() {
+            return link;
+        }
+
+        attempt += 1;
+    }
+}
+
+#[cfg(unix)]
+pub fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+pub fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    }
+    else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn create_symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "links aren't supported on this platform"))
+}
+
+// Finds a free name for a new entry inside `dir`, trying "name", "name 2", "name 3"...
+pub fn numbered_path(dir: &Path, name: &str) -> PathBuf {
+    let mut target = dir.join(name);
+    let mut attempt = 2;
+
+    while target.exists() {
+        target = dir.join(format!("{} {}", name, attempt));
+        attempt += 1;
+    }
+
+    target
+}
+
+pub fn copy_recursive(source: &Path, target: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(source)?;
+
+    if metadata.file_type().is_symlink() {
+        copy_symlink(source, target)?;
+    }
+    else if metadata.is_dir() {
+        std::fs::create_dir(target)?;
+
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &target.join(entry.file_name()))?;
+        }
+    }
+    else {
+        std::fs::copy(source, target)?;
+    }
+
+    Ok(())
+}
+
+// Makes `target` a link to wherever `source` points. Copying the link itself rather than what it
+// points to, which may be a folder or not exist at all.
+#[cfg(unix)]
+pub fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+pub fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link_target = std::fs::read_link(source)?;
+
+    // Relative targets are relative to the link, so this looks through `source` rather than at `link_target`.
+    if std::fs::metadata(source).map(| metadata | metadata.is_dir()).unwrap_or(false) {
+        std::os::windows::fs::symlink_dir(link_target, target)
+    }
+    else {
+        std::os::windows::fs::symlink_file(link_target, target)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn copy_symlink(_source: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "links aren't supported on this platform"))
+}
+
+// Moves `source` into `dest_dir`, keeping its name. Renaming doesn't work across filesystems,
+// in which case the entry gets copied over and the original removed.
+pub fn move_into(source: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+    if source.is_dir() && dest_dir.starts_with(source) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Cannot move a folder into itself"));
+    }
+
+    let target = dest_dir.join(source.file_name().unwrap_or_default());
+    move_entry(source, &target)?;
+
+    Ok(target)
+}
+
+// Moves `source` to exactly `target`, which must not exist yet.
+pub fn move_entry(source: &Path, target: &Path) -> io::Result<()> {
+    if target.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", target.display())));
+    }
+
+    match std::fs::rename(source, target) {
+        Ok(_) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            if let Err(e) = copy_recursive(source, target) {
+                // Don't leave half a copy behind, the original is still there.
+                let _ = remove_entry(target);
+                return Err(e);
+            }
+
+            remove_entry(source)
+        }
+        Err(e) => Err(e)
+    }
+}
+
+// Whether `source` can be renamed into `dest_dir`, instead of having to be copied over and removed.
+// Assumes it can when either of them can't be looked at.
+#[cfg(unix)]
+pub fn same_filesystem(source: &Path, dest_dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    // A link lives on the filesystem of its folder, whatever it points to.
+    match (std::fs::symlink_metadata(source), std::fs::metadata(dest_dir)) {
+        (Ok(source), Ok(dest_dir)) => source.dev() == dest_dir.dev(),
+        _ => true
+    }
+}
+
+#[cfg(windows)]
+pub fn same_filesystem(source: &Path, dest_dir: &Path) -> bool {
+    use std::path::Component;
+
+    let prefix = | path: &Path | {
+        std::fs::canonicalize(path).ok()
+            .and_then(| path | match path.components().next() {
+                Some(Component::Prefix(prefix)) => Some(prefix.as_os_str().to_ascii_lowercase()),
+                _ => None
+            })
+    };
+
+    match (prefix(source), prefix(dest_dir)) {
+        (Some(source), Some(dest_dir)) => source == dest_dir,
+        _ => true
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn same_filesystem(_source: &Path, _dest_dir: &Path) -> bool {
+    true
+}
+
+#[cfg(unix)]
+pub fn is_cross_device(error: &io::Error) -> bool {
+    // EXDEV
+    error.raw_os_error() == Some(18)
+}
+
+#[cfg(windows)]
+pub fn is_cross_device(error: &io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE
+    error.raw_os_error() == Some(17)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_cross_device(_error: &io::Error) -> bool {
+    false
+}
+
+// Whether the file can be launched as a program by itself.
+#[cfg(unix)]
+pub fn is_executable(_path: &Path, metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.is_file() && metadata.permissions().mode() & 0o100 != 0
+}
+
+#[cfg(windows)]
+pub fn is_executable(path: &Path, metadata: &std::fs::Metadata) -> bool {
+    let extension = path.extension().unwrap_or_default();
+    metadata.is_file() && ["exe", "bat", "cmd"].iter().any(| e | extension.eq_ignore_ascii_case(e))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_executable(_path: &Path, _metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+// Shown in the Permissions column, "rwxr-xr-x" style.
+#[cfg(unix)]
+pub fn permissions_string(metadata: &std::fs::Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    mode_string(metadata.permissions().mode())
+}
+
+// Windows has no modes, only a read-only flag.
+#[cfg(not(unix))]
+pub fn permissions_string(metadata: &std::fs::Metadata) -> String {
+    if metadata.permissions().readonly() { "r".to_string() } else { "rw".to_string() }
+}
+
+#[cfg(unix)]
+pub fn mode_string(mode: u32) -> String {
+    let flags = ['r', 'w', 'x'];
+
+    (0..9).map(| bit | {
+        if mode & (0o400 >> bit) != 0 { flags[bit % 3] } else { '-' }
+    }).collect()
+}
+
+// Names of the user and group owning an entry. Unix only.
+#[cfg(unix)]
+pub fn owner_and_group(metadata: &std::fs::Metadata) -> Option<(String, String)> {
+    use std::collections::HashMap;
+    use std::os::unix::fs::MetadataExt;
+    use std::sync::OnceLock;
+
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+
+    let users = USERS.get_or_init(|| read_id_names(Path::new("/etc/passwd")));
+    let groups = GROUPS.get_or_init(|| read_id_names(Path::new("/etc/group")));
+
+    // Accounts that come from somewhere else (LDAP and such) are shown by their id.
+    let owner = users.get(&metadata.uid()).cloned().unwrap_or_else(|| metadata.uid().to_string());
+    let group = groups.get(&metadata.gid()).cloned().unwrap_or_else(|| metadata.gid().to_string());
+
+    Some((owner, group))
+}
+
+#[cfg(not(unix))]
+pub fn owner_and_group(_metadata: &std::fs::Metadata) -> Option<(String, String)> {
+    None
+}
+
+// Both files have lines like "name:password:id:...".
+#[cfg(unix)]
+fn read_id_names(path: &Path) -> std::collections::HashMap<u32, String> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(| line | {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+// Kernel filesystems full of entries that report bogus sizes or block when read.
+#[cfg(target_os = "linux")]
+pub fn is_pseudo_fs(path: &Path) -> bool {
+    ["/proc", "/sys", "/dev"].iter().any(| root | path.starts_with(root))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_pseudo_fs(_path: &Path) -> bool {
+    false
+}
+
+// Files Windows keeps hidden as part of the OS, like pagefile.sys or desktop.ini.
+#[cfg(windows)]
+pub fn is_system_file(metadata: &std::fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+    let attributes = metadata.file_attributes();
+    attributes & FILE_ATTRIBUTE_HIDDEN != 0 && attributes & FILE_ATTRIBUTE_SYSTEM != 0
+}
+
+#[cfg(not(windows))]
+pub fn is_system_file(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+pub fn remove_entry(path: &Path) -> io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    }
+    else {
+        std::fs::remove_file(path)
+    }
+}
+
+// Total size in bytes and number of entries under `path`. Symlinks aren't followed.
+pub fn dir_size(path: &Path) -> (u64, usize) {
+    let mut size = 0;
+    let mut items = 0;
+
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = std::fs::symlink_metadata(entry.path()) {
+                items += 1;
+
+                if metadata.is_dir() {
+                    let (dir_size, dir_items) = dir_size(&entry.path());
+
+                    size += dir_size;
+                    items += dir_items;
+                }
+                else {
+                    size += metadata.len();
+                }
+            }
+        }
+    }
+
+    (size, items)
+}
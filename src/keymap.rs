@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+// Commands that can be bound to a key combination in the settings.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Action {
+    NewTab,
+    CloseTab,
+    FocusPath,
+    GoUp,
+    Refresh,
+    Rename,
+    CopyPath,
+    SelectAll,
+    ClearSelection,
+    Undo,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    CommandPalette,
+    GoHome,
+    NewFolder,
+    NewFile,
+    OpenTerminal,
+    OpenSettings,
+    ToggleFolderTree,
+    ToggleVolumes,
+    TogglePreview,
+    ToggleViewMode,
+    SortByName,
+    SortBySize,
+    SortByType,
+    SortByModified,
+    ReverseSortOrder,
+    Properties
+}
+
+impl Action {
+    pub const ALL: [Action; 29] = [
+        Action::NewTab,
+        Action::CloseTab,
+        Action::FocusPath,
+        Action::GoUp,
+        Action::Refresh,
+        Action::Rename,
+        Action::CopyPath,
+        Action::SelectAll,
+        Action::ClearSelection,
+        Action::Undo,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ResetZoom,
+        Action::CommandPalette,
+        Action::GoHome,
+        Action::NewFolder,
+        Action::NewFile,
+        Action::OpenTerminal,
+        Action::OpenSettings,
+        Action::ToggleFolderTree,
+        Action::ToggleVolumes,
+        Action::TogglePreview,
+        Action::ToggleViewMode,
+        Action::SortByName,
+        Action::SortBySize,
+        Action::SortByType,
+        Action::SortByModified,
+        Action::ReverseSortOrder,
+        Action::Properties
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::NewTab => "New tab",
+            Action::CloseTab => "Close tab",
+            Action::FocusPath => "Edit the path",
+            Action::GoUp => "Go up a level",
+            Action::Refresh => "Refresh",
+            Action::Rename => "Rename",
+            Action::CopyPath => "Copy path",
+            Action::SelectAll => "Select all",
+            Action::ClearSelection => "Clear selection",
+            Action::Undo => "Undo",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ResetZoom => "Reset zoom",
+            Action::CommandPalette => "Command palette",
+            Action::GoHome => "Go to the home folder",
+            Action::NewFolder => "New folder",
+            Action::NewFile => "New file",
+            Action::OpenTerminal => "Open a terminal here",
+            Action::OpenSettings => "Settings",
+            Action::ToggleFolderTree => "Show or hide the folder tree",
+            Action::ToggleVolumes => "Show or hide volumes",
+            Action::TogglePreview => "Show or hide the preview pane",
+            Action::ToggleViewMode => "Switch between list and grid view",
+            Action::SortByName => "Sort by name",
+            Action::SortBySize => "Sort by size",
+            Action::SortByType => "Sort by type",
+            Action::SortByModified => "Sort by date modified",
+            Action::ReverseSortOrder => "Reverse the sort order",
+            Action::Properties => "Properties"
+        }
+    }
+
+    // These work anywhere, the rest only while the file list has the keyboard.
+    pub fn is_global(&self) -> bool {
+        matches!(
+            self,
+            Action::NewTab | Action::CloseTab | Action::FocusPath | Action::ZoomIn | Action::ZoomOut | Action::ResetZoom
+                | Action::CommandPalette | Action::GoHome | Action::OpenSettings
+                | Action::ToggleFolderTree | Action::ToggleVolumes | Action::TogglePreview | Action::ToggleViewMode
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct KeyCombo {
+    pub modifiers: egui::Modifiers,
+    pub key: egui::Key
+}
+
+impl KeyCombo {
+    pub const fn new(modifiers: egui::Modifiers, key: egui::Key) -> KeyCombo {
+        KeyCombo {
+            modifiers,
+            key
+        }
+    }
+
+    // Removes the key press from the input if it matches, so nothing else reacts to it.
+    pub fn consume(&self, input: &mut egui::InputState) -> bool {
+        if input.consume_key(self.modifiers, self.key) {
+            return true;
+        }
+
+        // "+" and "=" share a key on most layouts.
+        if self.key == egui::Key::Plus && input.consume_key(self.modifiers, egui::Key::Equals) {
+            return true;
+        }
+
+        // Ctrl+C and Ctrl+X arrive as clipboard events rather than key presses.
+        let clipboard_event = match self.key {
+            egui::Key::C => egui::Event::Copy,
+            egui::Key::X => egui::Event::Cut,
+            _ => return false
+        };
+
+        let modifiers_match = self.modifiers.command
+            && input.modifiers.shift == self.modifiers.shift
+            && input.modifiers.alt == self.modifiers.alt
+        ;
+
+        if let Some(idx) = input.events.iter().position(| e | *e == clipboard_event).filter(| _ | modifiers_match) {
+            input.events.remove(idx);
+            return true;
+        }
+
+        false
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.mac_cmd {
+            write!(f, "Cmd+")?;
+        }
+        else if self.modifiers.command || self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+
+        write!(f, "{}", self.key.name())
+    }
+}
+
+pub fn default_keymap() -> HashMap<Action, KeyCombo> {
+    let command = egui::Modifiers::COMMAND;
+    let command_shift = egui::Modifiers { shift: true, ..egui::Modifiers::COMMAND };
+    let none = egui::Modifiers::NONE;
+
+    HashMap::from([
+        (Action::NewTab, KeyCombo::new(command, egui::Key::T)),
+        (Action::CloseTab, KeyCombo::new(command, egui::Key::W)),
+        (Action::FocusPath, KeyCombo::new(command, egui::Key::L)),
+        (Action::GoUp, KeyCombo::new(none, egui::Key::Backspace)),
+        (Action::Refresh, KeyCombo::new(none, egui::Key::F5)),
+        (Action::Rename, KeyCombo::new(none, egui::Key::F2)),
+        (Action::CopyPath, KeyCombo::new(command_shift, egui::Key::C)),
+        (Action::SelectAll, KeyCombo::new(command, egui::Key::A)),
+        (Action::ClearSelection, KeyCombo::new(none, egui::Key::Escape)),
+        (Action::Undo, KeyCombo::new(command, egui::Key::Z)),
+        (Action::ZoomIn, KeyCombo::new(command, egui::Key::Plus)),
+        (Action::ZoomOut, KeyCombo::new(command, egui::Key::Minus)),
+        (Action::ResetZoom, KeyCombo::new(command, egui::Key::Num0)),
+        (Action::CommandPalette, KeyCombo::new(command, egui::Key::P)),
+        (Action::GoHome, KeyCombo::new(egui::Modifiers::ALT, egui::Key::Home)),
+        (Action::NewFolder, KeyCombo::new(command_shift, egui::Key::N)),
+        (Action::Properties, KeyCombo::new(egui::Modifiers::ALT, egui::Key::Enter))
+    ])
+}
+
+// Other actions bound to the same combination as `action`.
+pub fn conflicts(keymap: &HashMap<Action, KeyCombo>, action: Action) -> Vec<Action> {
+    let combo = match keymap.get(&action) {
+        Some(combo) => combo,
+        None => return Vec::new()
+    };
+
+    Action::ALL.into_iter()
+        .filter(| other | *other != action && keymap.get(other) == Some(combo))
+        .collect()
+}
@@ -1,58 +1,607 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::path::PathBuf;
+mod archive;
+mod file_clipboard;
+mod file_types;
+mod fs_utils;
+mod keymap;
+mod palette;
+mod preview;
+mod privileges;
+mod properties;
+mod search;
+mod terminal;
+mod thumbnails;
+mod transfer;
+mod tree;
+mod undo;
+mod volumes;
+mod watcher;
+
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::io;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::time::{Instant, SystemTime};
 
 use eframe::egui;
 use egui_extras::TableBuilder;
 use time::Duration;
 use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq)]
+use archive::ArchiveTask;
+use keymap::{Action, KeyCombo};
+use palette::CommandPalette;
+use preview::Preview;
+use properties::PropertiesDialog;
+use search::SearchTask;
+use thumbnails::ThumbnailCache;
+use tree::DirTree;
+use transfer::TransferTask;
+use undo::FileOp;
+use volumes::Volume;
+use watcher::DirWatcher;
+
+#[derive(Clone, Copy, PartialEq)]
 enum EntryType {
     File,
     Folder,
     Symlink
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum ClipboardMode {
+    Copy,
+    Cut
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+enum ViewMode {
+    List,
+    Grid
+}
+
+// Things that can be done to a single entry, from its context menu or by double-clicking it.
+#[derive(Clone, Copy, PartialEq)]
+enum EntryAction {
+    Open,
+    Run,
+    // Index into the applications associated with the entry's extension.
+    OpenWith(usize),
+    ChooseApplication,
+    OpenInNewTab,
+    OpenInNewWindow,
+    ShowLinkTarget,
+    Cut,
+    Copy,
+    Paste,
+    PasteAsLink,
+    CreateLink,
+    Duplicate,
+    CopyTo,
+    MoveTo,
+    CopyPath,
+    CopyPathList,
+    CopyQuotedPaths,
+    CopyName,
+    Compress,
+    Extract,
+    Rename,
+    Remove,
+    Properties
+}
+
+// Things that can be done to the current directory, from the right-click menu of the empty space.
+#[derive(Clone, Copy, PartialEq)]
+enum BackgroundAction {
+    Paste,
+    PasteAsLink,
+    NewFolder,
+    NewFile,
+    OpenTerminal,
+    Refresh,
+    SelectAll,
+    InvertSelection,
+    ClearSelection
+}
+
+// Drag and drop payload, the index of the entry being dragged.
+struct DraggedEntry(usize);
+
+// Where the app opens on launch.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+enum StartupDir {
+    Home,
+    LastVisited,
+    Fixed(PathBuf)
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+enum Theme {
+    System,
+    Light,
+    Dark
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+enum TimeDisplay {
+    Relative,
+    Absolute
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+enum SortColumn {
+    Name,
+    Type,
+    Size,
+    Created,
+    Accessed,
+    Modified,
+    Permissions,
+    Owner,
+    Group
+}
+
+impl SortColumn {
+    const ALL: [SortColumn; 9] = [
+        SortColumn::Name,
+        SortColumn::Type,
+        SortColumn::Size,
+        SortColumn::Created,
+        SortColumn::Accessed,
+        SortColumn::Modified,
+        SortColumn::Permissions,
+        SortColumn::Owner,
+        SortColumn::Group
+    ];
+
+    // Owner and group are opt-in, most people are the only user of their machine.
+    const DEFAULT: [SortColumn; 7] = [
+        SortColumn::Name,
+        SortColumn::Type,
+        SortColumn::Size,
+        SortColumn::Created,
+        SortColumn::Accessed,
+        SortColumn::Modified,
+        SortColumn::Permissions
+    ];
+
+    // Windows doesn't have owners and groups in the Unix sense.
+    fn is_available(&self) -> bool {
+        !matches!(self, SortColumn::Owner | SortColumn::Group) || cfg!(unix)
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SortColumn::Name => "Name",
+            SortColumn::Type => "Type",
+            SortColumn::Size => "Size",
+            SortColumn::Created => "Creation date",
+            SortColumn::Accessed => "Last accessed",
+            SortColumn::Modified => "Last modified",
+            SortColumn::Permissions => "Permissions",
+            SortColumn::Owner => "Owner",
+            SortColumn::Group => "Group"
+        }
+    }
+
+    fn initial_width(&self) -> f32 {
+        match self {
+            SortColumn::Name => 300.0,
+            SortColumn::Size => 80.0,
+            _ => 100.0
+        }
+    }
+}
+
 struct EntryInfo {
     _type: EntryType,
+    // What a symlink resolves to. None for regular entries and broken links.
+    link_target_type: Option<EntryType>,
+    // Where a symlink points, as stored in the link.
+    link_target: Option<PathBuf>,
 
+    // Lossy copy of `file_name`, for display, sorting and filtering. Anything touching the disk goes through `path`.
     name: String,
+    file_name: OsString,
     path: PathBuf,
     extension: String,
+    // Detected once when the directory is read, sniffing the file on every repaint is too slow.
+    type_name: String,
+    category: Option<file_types::Category>,
+    media_type: Option<String>,
     length: usize,
     permissions: String,
+    // Unix only.
+    owner: Option<String>,
+    group: Option<String>,
+    executable: bool,
+
+    // Recursive size for folders, filled in by a background walker after the listing loads.
+    computed_size: Option<u64>,
+
+    // Relative times are worked out when drawn, so they keep up without reading the directory again.
+    modified_at: Option<SystemTime>,
+    accessed_at: Option<SystemTime>,
+    created_at: Option<SystemTime>
+}
+
+// What reading a directory on the background thread sends back. Entries come in batches,
+// so the first ones of a big directory show up while the rest are still being read.
+enum ListingUpdate {
+    // Everything in the directory, including what's past the entry limit.
+    Total(usize),
+    Entries(Vec<EntryInfo>),
+    // How many entries couldn't be read at all.
+    Finished(usize),
+    Failed(io::Error)
+}
+
+impl EntryInfo {
+    // Folders and symlinks that point at one can both be navigated into.
+    fn is_folder(&self) -> bool {
+        self._type == EntryType::Folder || self.link_target_type == Some(EntryType::Folder)
+    }
+
+    fn is_broken_link(&self) -> bool {
+        self._type == EntryType::Symlink && self.link_target_type.is_none()
+    }
+
+    // Names that aren't valid UTF-8 are shown with replacement characters.
+    fn has_invalid_name(&self) -> bool {
+        self.file_name.to_str().is_none()
+    }
+
+    // The name with the link target appended for symlinks.
+    fn display_name(&self) -> String {
+        match self.link_target.as_ref() {
+            Some(target) => format!("{} → {}", self.name, target.display()),
+            None => self.name.clone()
+        }
+    }
+}
+
+
+// Picks a glyph from the type detected when the directory was read.
+fn icon_for(entry: &EntryInfo) -> &'static str {
+    match entry._type {
+        EntryType::Folder => return "🗁",
+        EntryType::Symlink => return "🔗",
+        EntryType::File => {}
+    }
 
-    last_modified: Option<Duration>,
-    last_accessed: Option<Duration>,
-    last_modification: Option<Duration>
+    if entry.executable {
+        return "▶";
+    }
+
+    match entry.extension.to_lowercase().as_str() {
+        "pdf" => return "📕",
+        "exe" | "msi" | "bat" | "cmd" | "appimage" => return "⚙",
+        _ => {}
+    }
+
+    match entry.category {
+        Some(file_types::Category::Images) => "🖼",
+        Some(file_types::Category::Documents) => "🖹",
+        Some(file_types::Category::Audio) => "🎵",
+        Some(file_types::Category::Video) => "🎞",
+        Some(file_types::Category::Archives) => "📦",
+        Some(file_types::Category::Code) => "📜",
+        None => "🗋"
+    }
+}
+
+fn default_name_colors() -> HashMap<String, egui::Color32> {
+    let images = egui::Color32::from_rgb(90, 170, 90);
+    let archives = egui::Color32::from_rgb(220, 135, 45);
+    let executables = egui::Color32::from_rgb(215, 75, 75);
+
+    let groups = [
+        (images, ["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg"].as_slice()),
+        (archives, ["zip", "tar", "gz", "xz", "bz2", "7z", "rar"].as_slice()),
+        (executables, ["exe", "msi", "bat", "cmd", "sh", "appimage"].as_slice())
+    ];
+
+    groups.iter()
+        .flat_map(| (color, extensions) | extensions.iter().map(| extension | (extension.to_string(), *color)))
+        .collect()
+}
+
+// How many directories get their view state remembered before the least recently used ones are dropped.
+const VIEW_STATE_CAPACITY: usize = 256;
+// How many file operations can be undone.
+const UNDO_LIMIT: usize = 50;
+// How many directories the recent locations menu lists.
+const RECENT_DIRS_LIMIT: usize = 15;
+// Opening more files than this at once asks first, in case it was a misclick.
+const MAX_OPEN_WITHOUT_CONFIRMATION: usize = 10;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 3.0;
+const ZOOM_STEP: f32 = 0.1;
+
+// Sort order and scroll position of a directory, restored when navigating back to it.
+// The table only lets us scroll to a row, so the position is kept as the first visible row.
+#[derive(Clone, Copy)]
+struct ViewState {
+    sort_column: SortColumn,
+    sort_ascending: bool,
+    scroll_row: usize,
+
+    last_used: u64
 }
 
+// The browsing state of a tab. The active tab lives directly in `ExplorerApp`,
+// so its slot in `tabs` is only a placeholder until the user switches away from it.
+#[derive(Default)]
+struct TabState {
+    current_path: PathBuf,
+
+    filter_query: String,
+    selected_entry: Option<usize>,
+    selected_entries: BTreeSet<usize>,
+    parent_row_selected: bool,
+
+    previous_path: Vec<PathBuf>,
+    forward_path: Vec<PathBuf>,
+
+    current_dir_items: Vec<EntryInfo>,
+    dir_error: Option<String>,
+    entry_limit: usize,
+    total_entries: usize,
+
+    loading: bool,
+    dir_receiver: Option<Receiver<ListingUpdate>>,
+    incoming_items: Option<Vec<EntryInfo>>,
+    folder_size_receiver: Option<Receiver<(PathBuf, u64)>>
+}
 
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 struct ExplorerApp {
     initial_path: PathBuf,
     current_path: PathBuf,
+    startup_dir: StartupDir,
+
+    sort_column: SortColumn,
+    sort_ascending: bool,
+    // Remember the sort order of each directory, instead of using the same one everywhere.
+    sort_per_directory: bool,
+    folders_first: bool,
+    // Where folders go when sorting by size until their size is known, either way the sizes go.
+    size_sort_folders_first: bool,
+    // Compare runs of digits in names by their value, so "img2" comes before "img10".
+    natural_sort: bool,
+    // Applies to both sorting by name and filtering.
+    case_sensitive: bool,
+    // Adds a ".." row at the top of the list that goes up a level.
+    show_parent_row: bool,
+    // Double-clicking a folder opens it in a new tab, Ctrl+double-click stays in this one.
+    open_folders_in_new_tab: bool,
+    // Leaves out files the OS marks as its own. Only Windows has those.
+    hide_system_files: bool,
+    // Opening a linked folder goes to where it really is, instead of staying under the link.
+    follow_symlinks: bool,
+    // Folders with more entries than this are loaded in chunks of this size.
+    large_dir_threshold: usize,
+
+    // Visible columns of the list view, in display order. Name is always one of them.
+    columns: Vec<SortColumn>,
+    // Widths the user resized the list columns to.
+    column_widths: HashMap<SortColumn, f32>,
+    // Tint for the names of entries with these (lowercase) extensions.
+    name_colors: HashMap<String, egui::Color32>,
+    keymap: HashMap<Action, KeyCombo>,
+    // The action waiting for a new key combination in the settings.
+    #[serde(skip)]
+    rebinding_action: Option<Action>,
+    #[serde(skip)]
+    new_color_extension: String,
+
+    // Applications picked through "Open with", by lowercase extension. Most recently used first.
+    open_with: HashMap<String, Vec<PathBuf>>,
+
+    permanent_delete: bool,
+    // Moving to another drive copies everything and removes the originals afterwards, ask first.
+    confirm_cross_fs_moves: bool,
+    remember_history: bool,
+    time_display: TimeDisplay,
+    // KiB/MiB instead of KB/MB.
+    binary_units: bool,
+    theme: Theme,
+    show_preview: bool,
+    show_volumes: bool,
+    show_tree: bool,
+    dir_tree: DirTree,
+
+    view_mode: ViewMode,
+    grid_cell_size: f32,
+    // Extra height of list rows on top of the text.
+    row_padding: f32,
+    // Entries modified less than this many minutes ago get marked, 0 turns it off.
+    recent_change_minutes: u64,
+    zoom_factor: f32,
+    // The saved zoom is handed to egui on the first frame, afterwards egui's value is the source of truth.
+    #[serde(skip)]
+    zoom_applied: bool,
 
     #[serde(skip)]
     current_path_str: String,
     #[serde(skip)]
     editing_current_path: bool,
+    #[serde(skip)]
+    path_edit_mode: bool,
+    #[serde(skip)]
+    focus_path_edit: bool,
+    // Folders matching what's typed in the path box, and the text they were found for.
+    #[serde(skip)]
+    path_suggestions: Vec<String>,
+    #[serde(skip)]
+    path_suggestions_for: String,
+    #[serde(skip)]
+    path_suggestion: Option<usize>,
 
+    #[serde(skip)]
+    filter_query: String,
+    // Active quick filters, an entry is shown if it matches any of them.
+    #[serde(skip)]
+    category_filters: Vec<file_types::Category>,
+    // User defined quick filters, shown next to the built-in categories.
+    saved_filters: Vec<file_types::SavedFilter>,
+
+    // The last clicked entry, which is also the anchor for Shift+click ranges.
     #[serde(skip)]
     selected_entry: Option<usize>,
     #[serde(skip)]
+    selected_entries: BTreeSet<usize>,
+    // The ".." row, selected instead of any entry.
+    #[serde(skip)]
+    parent_row_selected: bool,
+    #[serde(skip)]
+    scroll_to_selected: bool,
+    // Entry to select once the directory being loaded is ready.
+    #[serde(skip)]
+    pending_select: Option<PathBuf>,
+    // Start renaming the entry in `pending_select` once it shows up.
+    #[serde(skip)]
+    pending_rename: bool,
+    // Characters typed over the list to jump to an entry, and when the last one was typed.
+    #[serde(skip)]
+    type_to_select: Option<(String, Instant)>,
+    #[serde(skip)]
     renaming_entry: Option<usize>,
     #[serde(skip)]
     renaming_string: String,
 
     #[serde(skip)]
-    previous_path: Vec<PathBuf>,
+    clipboard: Option<(Vec<PathBuf>, ClipboardMode)>,
+    // Whether `clipboard` is also on the system clipboard, and should go away once something else replaces it there.
+    #[serde(skip)]
+    clipboard_exported: bool,
+    // Reading the system clipboard waits on whichever program owns it, so it's done on a background thread.
+    #[serde(skip)]
+    clipboard_receiver: Option<Receiver<Option<(Vec<PathBuf>, ClipboardMode)>>>,
+
+    #[serde(skip)]
+    pending_delete: Option<Vec<PathBuf>>,
+    #[serde(skip)]
+    pending_delete_count: Option<usize>,
+    #[serde(skip)]
+    pending_delete_permanent: bool,
+    #[serde(skip)]
+    pending_delete_error: Option<String>,
+    // Files waiting for confirmation before all of them get opened.
+    #[serde(skip)]
+    pending_open: Option<Vec<PathBuf>>,
+    // Program waiting for confirmation before it gets launched.
+    #[serde(skip)]
+    pending_run: Option<PathBuf>,
+    // Entries and the folder on another filesystem they're waiting to be moved to.
+    #[serde(skip)]
+    pending_cross_fs_move: Option<(Vec<PathBuf>, PathBuf)>,
+
+    #[serde(skip)]
+    show_settings: bool,
+    #[serde(skip)]
+    command_palette: Option<CommandPalette>,
+
+    // Most recent operation last. Bounded by UNDO_LIMIT.
+    #[serde(skip)]
+    undo_stack: Vec<FileOp>,
+
+    #[serde(skip)]
+    // The message, when it was shown, and whether it's an error.
+    status_message: Option<(String, Instant, bool)>,
+
+    #[serde(skip)]
+    properties: Option<PropertiesDialog>,
+    #[serde(skip)]
+    preview: Option<Preview>,
+    #[serde(skip)]
+    thumbnails: ThumbnailCache,
+    #[serde(skip)]
+    archive_task: Option<ArchiveTask>,
+    #[serde(skip)]
+    transfer_task: Option<TransferTask>,
+
+    // Makes the filter box look for matches in subfolders too.
+    #[serde(skip)]
+    recursive_search: bool,
+    #[serde(skip)]
+    search_task: Option<SearchTask>,
+
+    // Explorers opened with "Open in new window", drawn as part of this one.
+    #[serde(skip)]
+    child_windows: HashMap<egui::ViewportId, ExplorerApp>,
+    #[serde(skip)]
+    windows_opened: u64,
+
+    // Available and total bytes on the volume of `current_path`.
+    #[serde(skip)]
+    disk_space: Option<(u64, u64)>,
+    // Where `current_path` really is, if getting there goes through a link.
+    #[serde(skip)]
+    resolved_path: Option<PathBuf>,
+
+    // Drives and mounted filesystems for the sidebar, listed again every few seconds
+    // so removable media shows up.
+    #[serde(skip)]
+    volumes: Vec<Volume>,
     #[serde(skip)]
+    volumes_receiver: Option<Receiver<Vec<Volume>>>,
+    #[serde(skip)]
+    volumes_listed_at: Option<Instant>,
+    // Home, Desktop, Documents and Downloads, looked up the first time the buttons are shown.
+    #[serde(skip)]
+    places: Option<[Option<PathBuf>; 4]>,
+
+    // Only written to storage when `remember_history` is enabled.
+    previous_path: Vec<PathBuf>,
     forward_path: Vec<PathBuf>,
+    // Most recent first, without duplicates.
+    recent_dirs: VecDeque<PathBuf>,
+
+    #[serde(skip)]
+    current_dir_items: Vec<EntryInfo>,
+    // Why the current directory couldn't be read. The last listing is kept around meanwhile.
+    #[serde(skip)]
+    dir_error: Option<String>,
+    // How many entries of the current directory get read, raised by "Load more".
+    #[serde(skip)]
+    entry_limit: usize,
+    #[serde(skip)]
+    total_entries: usize,
+
+    #[serde(skip)]
+    loading: bool,
+    #[serde(skip)]
+    dir_receiver: Option<Receiver<ListingUpdate>>,
+    // Entries of a reload, swapped in all at once when it's done so the list doesn't flicker.
+    // A newly opened directory shows its entries as they come instead.
+    #[serde(skip)]
+    incoming_items: Option<Vec<EntryInfo>>,
+    // Follows `current_path`, reloading the listing when other programs change it.
+    #[serde(skip)]
+    watcher: Option<DirWatcher>,
+    #[serde(skip)]
+    folder_size_receiver: Option<Receiver<(PathBuf, u64)>>,
+
+    #[serde(skip)]
+    scroll_row: usize,
+    #[serde(skip)]
+    pending_scroll_row: Option<usize>,
+    #[serde(skip)]
+    view_states: HashMap<PathBuf, ViewState>,
+    #[serde(skip)]
+    view_state_clock: u64,
 
     #[serde(skip)]
-    current_dir_items: Vec<EntryInfo>
+    tabs: Vec<TabState>,
+    #[serde(skip)]
+    active_tab: usize
 }
 
 impl Default for ExplorerApp {
@@ -65,510 +614,4538 @@ impl Default for ExplorerApp {
         ExplorerApp {
             initial_path,
             current_path,
+            startup_dir: StartupDir::LastVisited,
+
+            sort_column: SortColumn::Name,
+            sort_ascending: true,
+            sort_per_directory: true,
+            folders_first: true,
+            size_sort_folders_first: true,
+            natural_sort: true,
+            case_sensitive: false,
+            show_parent_row: false,
+            open_folders_in_new_tab: false,
+            hide_system_files: true,
+            follow_symlinks: true,
+            large_dir_threshold: 50_000,
+
+            columns: SortColumn::DEFAULT.to_vec(),
+            column_widths: HashMap::new(),
+            name_colors: default_name_colors(),
+            keymap: keymap::default_keymap(),
+            rebinding_action: None,
+            new_color_extension: String::new(),
+
+            open_with: HashMap::new(),
+
+            permanent_delete: false,
+            confirm_cross_fs_moves: true,
+            remember_history: true,
+            time_display: TimeDisplay::Relative,
+            binary_units: false,
+            theme: Theme::System,
+            show_preview: false,
+            show_volumes: true,
+            show_tree: false,
+            dir_tree: DirTree::default(),
+
+            view_mode: ViewMode::List,
+            grid_cell_size: 96.0,
+            row_padding: 10.0,
+            recent_change_minutes: 5,
+            zoom_factor: 1.0,
+            zoom_applied: false,
 
             current_path_str,
             editing_current_path: false,
+            path_edit_mode: false,
+            focus_path_edit: false,
+            path_suggestions: Vec::new(),
+            path_suggestions_for: String::new(),
+            path_suggestion: None,
+
+            filter_query: String::new(),
+            category_filters: Vec::new(),
+            saved_filters: Vec::new(),
 
             selected_entry: None,
+            selected_entries: BTreeSet::new(),
+            parent_row_selected: false,
+            scroll_to_selected: false,
+            pending_select: None,
+            pending_rename: false,
+            type_to_select: None,
             renaming_entry: None,
             renaming_string: String::new(),
 
+            clipboard: None,
+            clipboard_exported: false,
+            clipboard_receiver: None,
+
+            pending_delete: None,
+            pending_delete_count: None,
+            pending_delete_permanent: false,
+            pending_delete_error: None,
+            pending_open: None,
+            pending_run: None,
+            pending_cross_fs_move: None,
+
+            show_settings: false,
+            command_palette: None,
+
+            undo_stack: Vec::new(),
+
+            status_message: None,
+
+            properties: None,
+            preview: None,
+            thumbnails: ThumbnailCache::default(),
+            archive_task: None,
+            transfer_task: None,
+
+            recursive_search: false,
+            search_task: None,
+
+            child_windows: HashMap::new(),
+            windows_opened: 0,
+
+            disk_space: None,
+            resolved_path: None,
+
+            volumes: Vec::new(),
+            volumes_receiver: None,
+            volumes_listed_at: None,
+            places: None,
+
             previous_path: Vec::new(),
             forward_path: Vec::new(),
+            recent_dirs: VecDeque::new(),
+
+            current_dir_items: Vec::new(),
+            dir_error: None,
+            entry_limit: 0,
+            total_entries: 0,
+
+            loading: false,
+            dir_receiver: None,
+            incoming_items: None,
+            watcher: None,
+            folder_size_receiver: None,
+
+            scroll_row: 0,
+            pending_scroll_row: None,
+            view_states: HashMap::new(),
+            view_state_clock: 0,
 
-            current_dir_items: Vec::new()
+            tabs: vec![TabState::default()],
+            active_tab: 0
         }
     }
 }
 
 impl eframe::App for ExplorerApp {
-    #[cfg(feature = "persistence")]
-    fn save(&mut self, storage: &mut dyn epi::Storage) {
-        epi::set_value(storage, epi::APP_KEY, self);
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.remember_history {
+            eframe::set_value(storage, eframe::APP_KEY, self);
+        }
+        else {
+            let previous_path = std::mem::take(&mut self.previous_path);
+            let forward_path = std::mem::take(&mut self.forward_path);
+            let recent_dirs = std::mem::take(&mut self.recent_dirs);
+
+            eframe::set_value(storage, eframe::APP_KEY, self);
+
+            self.previous_path = previous_path;
+            self.forward_path = forward_path;
+            self.recent_dirs = recent_dirs;
+        }
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let dark_mode = match self.theme {
+            // Falls back to egui's own default when the OS doesn't say.
+            Theme::System => frame.info().system_theme.map(| theme | theme == eframe::Theme::Dark).unwrap_or(true),
+            Theme::Light => false,
+            Theme::Dark => true
+        };
+
+        if ctx.style().visuals.dark_mode != dark_mode {
+            ctx.set_visuals(if dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() });
+        }
+
+        if self.zoom_applied {
+            self.zoom_factor = ctx.zoom_factor();
+        }
+        else {
+            ctx.set_zoom_factor(self.zoom_factor);
+            self.zoom_applied = true;
+        }
+
         self.main_app(ctx);
     }
 }
 
 impl ExplorerApp {
+    fn restore(storage: Option<&dyn eframe::Storage>) -> ExplorerApp {
+        let mut app: ExplorerApp = storage
+            .and_then(| storage | eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default()
+        ;
+
+        let home = dirs::home_dir().expect("Failed to get home path");
+
+        app.current_path = match &app.startup_dir {
+            StartupDir::Home => home.clone(),
+            StartupDir::LastVisited => app.current_path.clone(),
+            StartupDir::Fixed(path) => path.clone()
+        };
+
+        // The directory might have been deleted or unmounted since the last run.
+        if !app.current_path.is_dir() {
+            app.current_path = home;
+        }
+
+        // Folders in the history could be gone as well.
+        app.previous_path.retain(| path | path.is_dir());
+        app.forward_path.retain(| path | path.is_dir());
+
+        // The settings could come from another OS.
+        app.columns.retain(| column | column.is_available());
+
+        if !app.sort_column.is_available() {
+            app.sort_column = SortColumn::Name;
+        }
+
+        if !app.columns.contains(&SortColumn::Name) {
+            app.columns.insert(0, SortColumn::Name);
+        }
+
+        // Actions added after the keymap was saved get their default shortcut.
+        for (action, combo) in keymap::default_keymap() {
+            app.keymap.entry(action).or_insert(combo);
+        }
+
+        app.current_path_str = app.current_path.to_str().unwrap_or_default().to_string();
+        app.update_dir_entries();
+
+        app.tabs = vec![TabState::default()];
+        app.active_tab = 0;
+
+        app
+    }
+
+    // Moves the active tab's state out of the app, leaving empty values behind.
+    fn stash_tab(&mut self) -> TabState {
+        TabState {
+            current_path: std::mem::take(&mut self.current_path),
+
+            filter_query: std::mem::take(&mut self.filter_query),
+            selected_entry: self.selected_entry.take(),
+            selected_entries: std::mem::take(&mut self.selected_entries),
+            parent_row_selected: std::mem::take(&mut self.parent_row_selected),
+
+            previous_path: std::mem::take(&mut self.previous_path),
+            forward_path: std::mem::take(&mut self.forward_path),
+
+            current_dir_items: std::mem::take(&mut self.current_dir_items),
+            dir_error: self.dir_error.take(),
+            entry_limit: self.entry_limit,
+            total_entries: self.total_entries,
+
+            loading: std::mem::take(&mut self.loading),
+            dir_receiver: self.dir_receiver.take(),
+            incoming_items: self.incoming_items.take(),
+            folder_size_receiver: self.folder_size_receiver.take()
+        }
+    }
+
+    fn load_tab(&mut self, tab: TabState) {
+        self.current_path = tab.current_path;
+        self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
+        self.editing_current_path = false;
+
+        self.filter_query = tab.filter_query;
+        self.selected_entry = tab.selected_entry;
+        self.selected_entries = tab.selected_entries;
+        self.parent_row_selected = tab.parent_row_selected;
+        self.renaming_entry = None;
+        self.renaming_string = String::new();
+
+        self.previous_path = tab.previous_path;
+        self.forward_path = tab.forward_path;
+
+        self.current_dir_items = tab.current_dir_items;
+        self.dir_error = tab.dir_error;
+        self.entry_limit = tab.entry_limit;
+        self.total_entries = tab.total_entries;
+
+        self.loading = tab.loading;
+        self.dir_receiver = tab.dir_receiver;
+        self.incoming_items = tab.incoming_items;
+        self.disk_space = ExplorerApp::disk_space(&self.current_path);
+        self.folder_size_receiver = tab.folder_size_receiver;
+    }
+
+    fn switch_tab(&mut self, idx: usize) {
+        if idx == self.active_tab || idx >= self.tabs.len() {
+            return;
+        }
+
+        self.save_view_state();
+        self.tabs[self.active_tab] = self.stash_tab();
+
+        let tab = std::mem::take(&mut self.tabs[idx]);
+        self.load_tab(tab);
+        self.active_tab = idx;
+
+        self.restore_view_state();
+        self.sort_entries();
+    }
+
+    fn open_tab(&mut self, path: PathBuf) {
+        self.save_view_state();
+        self.tabs[self.active_tab] = self.stash_tab();
+        self.tabs.push(TabState::default());
+        self.active_tab = self.tabs.len() - 1;
+
+        self.load_tab(TabState { current_path: path, ..Default::default() });
+        self.restore_view_state();
+        self.update_dir_entries();
+    }
+
+    fn close_tab(&mut self, idx: usize) {
+        // There's always at least one tab around.
+        if self.tabs.len() <= 1 || idx >= self.tabs.len() {
+            return;
+        }
+
+        if idx == self.active_tab {
+            self.tabs.remove(idx);
+            self.active_tab = idx.min(self.tabs.len() - 1);
+
+            let tab = std::mem::take(&mut self.tabs[self.active_tab]);
+            self.load_tab(tab);
+
+            self.restore_view_state();
+            self.sort_entries();
+        }
+        else {
+            self.tabs.remove(idx);
+
+            if idx < self.active_tab {
+                self.active_tab -= 1;
+            }
+        }
+    }
+
+    fn tab_title(path: &Path) -> String {
+        match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => path.display().to_string()
+        }
+    }
+
     fn change_dir(&mut self, new_path: PathBuf) {
-        self.selected_entry = None;
+        self.clear_selection();
+        self.filter_query.clear();
+        self.save_view_state();
         self.previous_path.push(self.current_path.clone());
 
         self.current_path = new_path;
         self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
 
+        self.recent_dirs.retain(| path | *path != self.current_path);
+        self.recent_dirs.push_front(self.current_path.clone());
+        self.recent_dirs.truncate(RECENT_DIRS_LIMIT);
+
+        self.restore_view_state();
         self.update_dir_entries();
     }
 
+    // Goes to the folder `path` is in and selects it, scrolling it into view.
+    fn reveal(&mut self, path: &Path) {
+        let parent = match path.parent() {
+            Some(parent) => parent,
+            None => {
+                self.change_dir(path.to_path_buf());
+                return;
+            }
+        };
+
+        if parent != self.current_path {
+            self.change_dir(parent.to_path_buf());
+            self.pending_select = Some(path.to_path_buf());
+            return;
+        }
+
+        // It could be hidden by the filter, or by the search that found it.
+        self.filter_query.clear();
+        self.search_task = None;
+
+        match self.current_dir_items.iter().position(| entry | entry.path == path).filter(| _ | self.dir_receiver.is_none()) {
+            Some(idx) => {
+                self.select_only(idx);
+                self.scroll_to_selected = true;
+            }
+            None => {
+                // Not listed yet, most likely it was just created.
+                self.refresh_dir();
+                self.pending_select = Some(path.to_path_buf());
+            }
+        }
+    }
+
     fn previous_dir(&mut self) {
         if let Some(target_path) = self.previous_path.pop() {
+            self.save_view_state();
             self.forward_path.push(self.current_path.clone());
             self.current_path = target_path;
             self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
 
-            self.selected_entry = None;
+            self.clear_selection();
+            self.filter_query.clear();
+            self.restore_view_state();
             self.update_dir_entries();
         }
     }
 
     fn forward_dir(&mut self) {
         if let Some(target_path) = self.forward_path.pop() {
+            self.save_view_state();
             self.previous_path.push(self.current_path.clone());
             self.current_path = target_path;
             self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
 
-            self.selected_entry = None;
+            self.clear_selection();
+            self.filter_query.clear();
+            self.restore_view_state();
             self.update_dir_entries();
         }
     }
 
     fn previous_level(&mut self) {
-        if let Some(parent) = self.current_path.parent() {
+        if let Some(parent) = self.current_path.parent().map(| p | p.to_path_buf()) {
+            self.save_view_state();
             self.previous_path.push(self.current_path.clone());
-            self.current_path = parent.to_path_buf();
+            self.current_path = parent;
             self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
 
-            self.selected_entry = None;
+            self.clear_selection();
+            self.filter_query.clear();
+            self.restore_view_state();
             self.update_dir_entries();
         }
     }
 
-    fn refresh_dir(&mut self) {
-        self.selected_entry = None;
-        self.update_dir_entries();
-    }
+    fn save_view_state(&mut self) {
+        self.view_state_clock += 1;
 
-    fn main_app(&mut self, ctx: &egui::Context) {
-        if self.current_path_str.is_empty() {
-            self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
-        }
+        // With a global sort order, directories keep the one they had from before it was turned on.
+        let (sort_column, sort_ascending) = self.view_states.get(&self.current_path)
+            .filter(| _ | !self.sort_per_directory)
+            .map(| state | (state.sort_column, state.sort_ascending))
+            .unwrap_or((self.sort_column, self.sort_ascending))
+        ;
+
+        let state = ViewState {
+            sort_column,
+            sort_ascending,
+            scroll_row: self.scroll_row,
+
+            last_used: self.view_state_clock
+        };
+
+        self.view_states.insert(self.current_path.clone(), state);
+
+        if self.view_states.len() > VIEW_STATE_CAPACITY {
+            let oldest = self.view_states.iter()
+                .min_by_key(| (_, state) | state.last_used)
+                .map(| (path, _) | path.clone())
+            ;
+
+            if let Some(oldest) = oldest {
+                self.view_states.remove(&oldest);
+            }
+        }
+    }
+
+    // Applies the remembered view state for the current path. Directories we haven't seen yet
+    // keep the current sort order and start scrolled to the top, as do all of them with a global sort order.
+    fn restore_view_state(&mut self) {
+        self.view_state_clock += 1;
+
+        if let Some(state) = self.view_states.get_mut(&self.current_path) {
+            state.last_used = self.view_state_clock;
+
+            if self.sort_per_directory {
+                self.sort_column = state.sort_column;
+                self.sort_ascending = state.sort_ascending;
+            }
+
+            self.scroll_row = state.scroll_row;
+        }
+        else {
+            self.scroll_row = 0;
+        }
+
+        self.pending_scroll_row = Some(self.scroll_row);
+    }
+
+    fn category_filter_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(| ui | {
+            for category in file_types::Category::ALL {
+                let active = self.category_filters.contains(&category);
+
+                if ui.selectable_label(active, category.label()).clicked() {
+                    if active {
+                        self.category_filters.retain(| c | *c != category);
+                    }
+                    else {
+                        self.category_filters.push(category);
+                    }
+                }
+            }
+
+            if !self.saved_filters.is_empty() {
+                ui.separator();
+
+                for filter in self.saved_filters.iter_mut() {
+                    ui.toggle_value(&mut filter.active, &filter.name);
+                }
+            }
+
+            let saved_filter_active = self.saved_filters.iter().any(| filter | filter.active);
+
+            if !self.category_filters.is_empty() || saved_filter_active || !self.filter_query.is_empty() {
+                ui.separator();
+
+                if ui.small_button("✖ Clear filters").clicked() {
+                    self.category_filters.clear();
+                    self.filter_query.clear();
+
+                    for filter in self.saved_filters.iter_mut() {
+                        filter.active = false;
+                    }
+                }
+            }
+        });
+    }
+
+    fn push_undo(&mut self, op: FileOp) {
+        if self.undo_stack.len() >= UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+
+        self.undo_stack.push(op);
+    }
+
+    fn undo(&mut self) {
+        match self.undo_stack.pop() {
+            Some(op) => {
+                if let Err(e) = op.undo() {
+                    self.set_error(format!("Couldn't undo: {}", e));
+                }
+
+                self.refresh_dir();
+            }
+            None => self.set_error("Nothing to undo".to_string())
+        }
+    }
+
+    fn refresh_dir(&mut self) {
+        self.clear_selection();
+
+        // Only the watcher's reloads keep folder sizes, it can't see changes inside the folders themselves.
+        for entry in self.current_dir_items.iter_mut() {
+            entry.computed_size = None;
+        }
+
+        // The old rows stay up until the new listing is complete, so the list doesn't jump around or lose its scroll position.
+        self.reload_dir_entries();
+    }
+
+    fn main_app(&mut self, ctx: &egui::Context) {
+        self.poll_dir_entries(ctx);
+        self.poll_watcher(ctx);
+
+        // Files could have been copied elsewhere while the window was in the background.
+        if ctx.input(| i | i.events.contains(&egui::Event::WindowFocused(true))) {
+            self.import_system_clipboard(ctx);
+        }
+
+        self.poll_system_clipboard();
+
+        self.poll_folder_sizes(ctx);
+        self.poll_archive_task(ctx);
+        self.poll_transfer_task(ctx);
+        self.poll_volumes(ctx);
+        self.thumbnails.poll(ctx);
+        self.update_search(ctx);
+        self.handle_dropped_files(ctx);
+
+        if self.current_path_str.is_empty() {
+            self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
+        }
+
+        // Keep the rest of the window inert while a confirmation dialog is up.
+        let dialog_open = self.pending_delete.is_some() || self.pending_open.is_some() || self.pending_run.is_some() || self.pending_cross_fs_move.is_some() || self.transfer_task.as_ref().map(| task | task.is_large()).unwrap_or(false);
+
+        if !dialog_open {
+            self.handle_list_keys(ctx);
+            self.handle_shortcuts(ctx);
+        }
+
+        egui::TopBottomPanel::top("current_path").show(ctx, |ui| {
+            ui.set_enabled(!dialog_open);
+
+            if privileges::is_elevated() {
+                ExplorerApp::elevated_banner(ui);
+            }
+
+            self.tab_bar(ui);
+
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(!self.previous_path.is_empty(), |ui| {
+                    if ui.small_button("⏴").clicked() {
+                        self.previous_dir();
+                    }
+                });
+
+                ui.add_enabled_ui(!self.forward_path.is_empty(), |ui| {
+                    if ui.small_button("⏵").clicked() {
+                        self.forward_dir();
+                    }
+                });
+
+                ui.add_enabled_ui(self.current_path.parent().is_some(), |ui| {
+                    if ui.small_button("⏶").clicked() {
+                        self.previous_level();
+                    }
+                });
+
+                ui.menu_button("⏷", | ui | {
+                    self.recent_dirs_menu(ui);
+                }).response.on_hover_text("Recent locations");
+
+                ui.separator();
+
+                self.places_buttons(ui);
+
+                ui.separator();
+
+                if ui.small_button("↻").clicked() {
+                    self.refresh_dir();
+                }
+
+                ui.toggle_value(&mut self.show_volumes, "🖴").on_hover_text("Volumes");
+                ui.toggle_value(&mut self.show_tree, "🌲").on_hover_text("Folder tree");
+                ui.toggle_value(&mut self.show_preview, "👁").on_hover_text("Preview pane");
+
+                ui.separator();
+
+                ui.selectable_value(&mut self.view_mode, ViewMode::List, "☰").on_hover_text("List view");
+                ui.selectable_value(&mut self.view_mode, ViewMode::Grid, "⊞").on_hover_text("Grid view");
+
+                ui.menu_button("⇅", | ui | {
+                    self.sort_menu(ui);
+                }).response.on_hover_text("Sort");
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), | ui | {
+                    ui.toggle_value(&mut self.show_settings, "⚙").on_hover_text("Settings");
+
+                    if ui.toggle_value(&mut self.case_sensitive, "Aa").on_hover_text("Match case").changed() {
+                        self.sort_entries();
+                    }
+
+                    ui.toggle_value(&mut self.recursive_search, "⤵").on_hover_text("Search subfolders");
+
+                    let filter_text = egui::TextEdit::singleline(&mut self.filter_query)
+                        .hint_text("🔍 Filter")
+                        .desired_width(150.0)
+                    ;
+
+                    ui.add(filter_text);
+
+                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), | ui | {
+                        self.path_bar(ui);
+                    });
+                });
+            });
+
+            self.category_filter_bar(ui);
+        });
+
+        self.status_bar(ctx);
+
+        if self.show_volumes {
+            egui::SidePanel::left("volumes_panel").resizable(true).default_width(180.0).show(ctx, | ui | {
+                ui.set_enabled(!dialog_open);
+                self.volumes_sidebar(ui);
+            });
+        }
+
+        if self.show_tree {
+            egui::SidePanel::left("tree_panel").resizable(true).default_width(200.0).show(ctx, | ui | {
+                ui.set_enabled(!dialog_open);
+
+                if let Some(target) = self.dir_tree.show(ui, &self.current_path) {
+                    self.change_dir(target);
+                }
+            });
+        }
+
+        if self.show_preview {
+            self.update_preview();
+
+            egui::SidePanel::right("preview_panel").resizable(true).default_width(250.0).show(ctx, | ui | {
+                ui.set_enabled(!dialog_open);
+
+                if let Some(preview) = self.preview.as_mut() {
+                    preview.show(ui, self.binary_units);
+                }
+                else {
+                    ui.centered_and_justified(| ui | {
+                        ui.weak("Nothing selected");
+                    });
+                }
+            });
+        }
+        else {
+            self.preview = None;
+        }
+
+        let central_panel = egui::CentralPanel::default().show(ctx, |ui| {
+            ui.set_enabled(!dialog_open);
+
+            if self.loading {
+                ui.centered_and_justified(| ui | {
+                    ui.spinner();
+                });
+            }
+            else if let Some(error) = self.dir_error.clone() {
+                ui.vertical_centered(| ui | {
+                    ui.add_space(ui.available_height() / 3.0);
+                    ui.heading(format!("Cannot open this folder: {}", error));
+                    ui.add_space(8.0);
+
+                    if ui.button("⏴ Go back").clicked() {
+                        if self.previous_path.is_empty() {
+                            self.previous_level();
+                        }
+                        else {
+                            self.previous_dir();
+                        }
+                    }
+                });
+            }
+            else if self.search_task.is_some() {
+                self.search_results(ui);
+            }
+            else {
+                if self.entry_limit < self.total_entries {
+                    self.load_more_bar(ui);
+                }
+
+                match self.view_mode {
+                    ViewMode::List => {
+                        egui::ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| {
+                            self.fill_files_table(ui);
+                        });
+                    }
+                    ViewMode::Grid => {
+                        egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                            self.fill_files_grid(ui);
+                        });
+                    }
+                }
+
+                // Drawn over the list, so the empty space keeps its context menu for pasting.
+                let placeholder = {
+                    if self.current_dir_items.is_empty() {
+                        Some("This folder is empty")
+                    }
+                    else if self.visible_entries().is_empty() {
+                        Some("Nothing here matches the current filters")
+                    }
+                    else {
+                        None
+                    }
+                };
+
+                if let Some(placeholder) = placeholder {
+                    ui.painter().text(
+                        ui.max_rect().center(),
+                        egui::Align2::CENTER_CENTER,
+                        placeholder,
+                        egui::TextStyle::Heading.resolve(ui.style()),
+                        ui.visuals().weak_text_color()
+                    );
+                }
+            }
+        });
+
+        if ctx.input(| i | !i.raw.hovered_files.is_empty()) {
+            let rect = central_panel.response.rect;
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("file_drop_overlay")));
+
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                format!("Drop to copy into {}", ExplorerApp::tab_title(&self.current_path)),
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE
+            );
+        }
+
+        self.delete_confirmation_dialog(ctx);
+        self.open_confirmation_dialog(ctx);
+        self.run_confirmation_dialog(ctx);
+        self.cross_fs_move_dialog(ctx);
+        self.settings_window(ctx);
+        self.command_palette(ctx);
+        self.transfer_dialog(ctx);
+
+        if let Some(properties) = self.properties.as_mut() {
+            if !properties.show(ctx, self.binary_units) {
+                self.properties = None;
+            }
+        }
+
+        self.show_child_windows(ctx);
+    }
+
+    // A separate explorer for another window. It shares this one's preferences, but not its history.
+    fn new_window(&self, path: PathBuf) -> ExplorerApp {
+        let mut window = ExplorerApp {
+            current_path_str: path.to_str().unwrap_or_default().to_string(),
+            current_path: path,
+
+            sort_column: self.sort_column,
+            sort_ascending: self.sort_ascending,
+            sort_per_directory: self.sort_per_directory,
+            folders_first: self.folders_first,
+            size_sort_folders_first: self.size_sort_folders_first,
+            natural_sort: self.natural_sort,
+            case_sensitive: self.case_sensitive,
+            show_parent_row: self.show_parent_row,
+            open_folders_in_new_tab: self.open_folders_in_new_tab,
+            hide_system_files: self.hide_system_files,
+            follow_symlinks: self.follow_symlinks,
+            large_dir_threshold: self.large_dir_threshold,
+
+            columns: self.columns.clone(),
+            column_widths: self.column_widths.clone(),
+            name_colors: self.name_colors.clone(),
+            keymap: self.keymap.clone(),
+            open_with: self.open_with.clone(),
+            saved_filters: self.saved_filters.clone(),
+
+            permanent_delete: self.permanent_delete,
+            confirm_cross_fs_moves: self.confirm_cross_fs_moves,
+            time_display: self.time_display,
+            binary_units: self.binary_units,
+            show_preview: self.show_preview,
+            show_volumes: self.show_volumes,
+            show_tree: self.show_tree,
+            dir_tree: self.dir_tree.clone(),
+
+            view_mode: self.view_mode,
+            grid_cell_size: self.grid_cell_size,
+            row_padding: self.row_padding,
+            recent_change_minutes: self.recent_change_minutes,
+
+            ..ExplorerApp::default()
+        };
+
+        // Only the filters themselves are shared, not which ones are on.
+        for filter in window.saved_filters.iter_mut() {
+            filter.active = false;
+        }
+
+        window.update_dir_entries();
+        window
+    }
+
+    // Every window is drawn from here each frame, and keeps its own state between frames.
+    fn show_child_windows(&mut self, ctx: &egui::Context) {
+        self.child_windows.retain(| id, window | {
+            let builder = egui::ViewportBuilder::default()
+                .with_title(format!("{} - explorer-rs", ExplorerApp::tab_title(&window.current_path)))
+            ;
+
+            ctx.show_viewport_immediate(*id, builder, | ctx, _ | {
+                window.main_app(ctx);
+                !ctx.input(| i | i.viewport().close_requested())
+            })
+        });
+    }
+
+    // Only reloads the preview when the selection points at a different entry.
+    fn update_preview(&mut self) {
+        let selected_path = self.selected_entry.and_then(| idx | self.current_dir_items.get(idx)).map(| entry | entry.path.clone());
+
+        if selected_path.as_deref() != self.preview.as_ref().map(| preview | preview.path()) {
+            self.preview = selected_path.map(| path | Preview::new(&path));
+        }
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped_files: Vec<PathBuf> = ctx.input(| i | {
+            i.raw.dropped_files.iter().filter_map(| file | file.path.clone()).collect()
+        });
+
+        if dropped_files.is_empty() {
+            return;
+        }
+
+        self.start_transfer(dropped_files, self.current_path.clone(), ClipboardMode::Copy);
+    }
+
+    fn path_bar(&mut self, ui: &mut egui::Ui) {
+        if self.path_edit_mode {
+            if self.editing_current_path {
+                let typed_path = PathBuf::from(&self.current_path_str);
+                let dark_mode = ui.visuals().dark_mode;
+
+                // Green for folders, blue for files that would get opened instead.
+                // Pure colours are unreadable on the light theme's white background.
+                if typed_path.is_dir() {
+                    let color = if dark_mode { egui::Color32::from_rgb(0, 255, 0) } else { egui::Color32::from_rgb(0, 128, 0) };
+                    ui.visuals_mut().override_text_color = Some(color);
+                }
+                else if typed_path.exists() {
+                    let color = if dark_mode { egui::Color32::from_rgb(100, 180, 255) } else { egui::Color32::from_rgb(0, 90, 200) };
+                    ui.visuals_mut().override_text_color = Some(color);
+                }
+                else {
+                    ui.visuals_mut().override_text_color = Some(ui.visuals().error_fg_color);
+                }
+            }
+
+            if self.editing_current_path {
+                self.update_path_suggestions();
+            }
+            else {
+                self.path_suggestions.clear();
+                self.path_suggestions_for.clear();
+            }
+
+            let has_suggestions = !self.path_suggestions.is_empty();
+            let mut accept_suggestion = false;
+
+            if has_suggestions {
+                let (up, down, tab, enter) = ui.input_mut(| i | {
+                    (
+                        i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                        i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                        i.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+                        i.key_pressed(egui::Key::Enter)
+                    )
+                });
+
+                let last = self.path_suggestions.len() - 1;
+
+                self.path_suggestion = match self.path_suggestion {
+                    Some(idx) if up => Some(idx.saturating_sub(1)),
+                    Some(idx) if down => Some((idx + 1).min(last)),
+                    None if down => Some(0),
+                    None if up => Some(last),
+                    current => current
+                };
+
+                accept_suggestion = tab;
+
+                // Enter goes straight into the highlighted folder.
+                if enter {
+                    if let Some(suggestion) = self.path_suggestion.and_then(| idx | self.path_suggestions.get(idx)) {
+                        self.current_path_str = suggestion.clone();
+                    }
+                }
+            }
+
+            // Keep Tab from moving the focus away while there's something to complete.
+            let path_text = egui::TextEdit::singleline(&mut self.current_path_str)
+                .desired_width(ui.available_width())
+                .lock_focus(has_suggestions)
+            ;
+
+            let path_text = ui.add(path_text);
+
+            if accept_suggestion {
+                let suggestion = self.path_suggestion.unwrap_or(0);
+
+                if let Some(suggestion) = self.path_suggestions.get(suggestion) {
+                    self.current_path_str = format!("{}{}", suggestion, std::path::MAIN_SEPARATOR);
+
+                    // Continue typing after the completed folder.
+                    if let Some(mut state) = egui::text_edit::TextEditState::load(ui.ctx(), path_text.id) {
+                        let cursor = egui::text::CCursor::new(self.current_path_str.chars().count());
+                        state.set_ccursor_range(Some(egui::text::CCursorRange::one(cursor)));
+                        state.store(ui.ctx(), path_text.id);
+                    }
+                }
+            }
+
+            if has_suggestions && path_text.has_focus() {
+                let highlighted = self.path_suggestion;
+
+                egui::Area::new(ui.id().with("path_suggestions"))
+                    .order(egui::Order::Foreground)
+                    .fixed_pos(path_text.rect.left_bottom())
+                    .show(ui.ctx(), | ui | {
+                        egui::Frame::popup(ui.style()).show(ui, | ui | {
+                            ui.set_min_width(path_text.rect.width());
+
+                            for (idx, suggestion) in self.path_suggestions.iter().enumerate() {
+                                ui.selectable_label(highlighted == Some(idx), suggestion);
+                            }
+                        });
+                    })
+                ;
+            }
+
+            if self.focus_path_edit {
+                path_text.request_focus();
+                self.focus_path_edit = false;
+
+                // Select the whole path, so typing replaces it.
+                let mut state = egui::text_edit::TextEditState::load(ui.ctx(), path_text.id).unwrap_or_default();
+                let end = egui::text::CCursor::new(self.current_path_str.chars().count());
+                state.set_ccursor_range(Some(egui::text::CCursorRange::two(egui::text::CCursor::new(0), end)));
+                state.store(ui.ctx(), path_text.id);
+            }
+
+            self.editing_current_path = path_text.has_focus();
+
+            if path_text.lost_focus() {
+                let typed_path = PathBuf::from(&self.current_path_str);
+                // Losing the focus any other way, like clicking elsewhere, leaves the path as it was.
+                let committed = ui.input(ExplorerApp::enter_pressed);
+
+                if committed && typed_path.is_dir() {
+                    self.change_dir(typed_path);
+                }
+                else {
+                    if committed {
+                        // Files get opened, staying in the current directory.
+                        if typed_path.exists() {
+                            open::that_in_background(&typed_path);
+                        }
+                        else {
+                            self.set_error(format!("{} doesn't exist", typed_path.display()));
+                        }
+                    }
+
+                    self.current_path_str = self.current_path.to_str().unwrap_or_default().to_string();
+                }
+
+                self.path_edit_mode = false;
+            }
+
+            ui.visuals_mut().override_text_color = None;
+        }
+        else {
+            let mut target = None;
+            let mut start_editing = false;
+
+            egui::ScrollArea::horizontal().id_source("breadcrumbs").stick_to_right(true).show(ui, | ui | {
+                ui.horizontal(| ui | {
+                    let ancestors: Vec<&Path> = self.current_path.ancestors().collect();
+
+                    for (idx, ancestor) in ancestors.iter().rev().enumerate() {
+                        if idx > 0 {
+                            ui.label("›");
+                        }
+
+                        // The root has no file name, show it as-is ("/" or the drive, like "C:\").
+                        let label = match ancestor.file_name() {
+                            Some(name) => name.to_string_lossy().to_string(),
+                            None => ancestor.display().to_string()
+                        };
+
+                        let is_current = *ancestor == self.current_path.as_path();
+
+                        if ui.selectable_label(is_current, label).clicked() {
+                            if is_current {
+                                start_editing = true;
+                            }
+                            else {
+                                target = Some(ancestor.to_path_buf());
+                            }
+                        }
+                    }
+                });
+            });
+
+            if let Some(resolved) = self.resolved_path.as_ref() {
+                if ui.small_button("🔗").on_hover_text(format!("Through a link, this folder is {}", resolved.display())).clicked() {
+                    target = Some(resolved.clone());
+                }
+            }
+
+            if ui.small_button("✏").on_hover_text("Edit path").clicked() {
+                start_editing = true;
+            }
+
+            if start_editing {
+                self.path_edit_mode = true;
+                self.focus_path_edit = true;
+            }
+
+            if let Some(target) = target {
+                self.change_dir(target);
+            }
+        }
+    }
+
+    // Runs whatever the pressed key combination is bound to in the keymap.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // The next key press is meant for the settings while rebinding.
+        if self.rebinding_action.is_some() {
+            return;
+        }
+
+        // Typing in the path box or renaming an entry takes priority over the file list.
+        let list_has_keyboard = !ctx.wants_keyboard_input() && self.renaming_entry.is_none();
+
+        let pressed: Vec<Action> = ctx.input_mut(| i | {
+            Action::ALL.into_iter()
+                .filter(| action | action.is_global() || list_has_keyboard)
+                .filter(| action | self.keymap.get(action).map(| combo | combo.consume(i)).unwrap_or(false))
+                .collect()
+        });
+
+        for action in pressed {
+            self.run_action(ctx, action);
+        }
+
+        // Browsers have Alt+D for the address bar too, whatever Ctrl+L got rebound to.
+        if self.renaming_entry.is_none() && ctx.input_mut(| i | i.consume_key(egui::Modifiers::ALT, egui::Key::D)) {
+            self.run_action(ctx, Action::FocusPath);
+        }
+    }
+
+    fn run_action(&mut self, ctx: &egui::Context, action: Action) {
+        match action {
+            Action::NewTab => self.open_tab(dirs::home_dir().expect("Failed to get home path")),
+            Action::CloseTab => self.close_tab(self.active_tab),
+            Action::FocusPath => {
+                if self.renaming_entry.is_none() {
+                    self.path_edit_mode = true;
+                    self.focus_path_edit = true;
+                    self.editing_current_path = true;
+                }
+            }
+            Action::GoUp => self.previous_level(),
+            Action::Refresh => self.refresh_dir(),
+            Action::Rename => {
+                if let Some(selected) = self.selected_entry {
+                    if let Some(entry) = self.current_dir_items.get(selected) {
+                        self.renaming_entry = Some(selected);
+                        self.renaming_string = entry.name.clone();
+                    }
+                }
+            }
+            Action::CopyPath => {
+                if let Some(entry) = self.selected_entry.and_then(| selected | self.current_dir_items.get(selected)) {
+                    ctx.output_mut(| o | o.copied_text = entry.path.to_string_lossy().to_string());
+                }
+            }
+            Action::SelectAll => self.select_all(),
+            Action::ClearSelection => self.clear_selection(),
+            Action::Undo => self.undo(),
+            Action::ZoomIn => self.set_zoom(ctx, self.zoom_factor + ZOOM_STEP),
+            Action::ZoomOut => self.set_zoom(ctx, self.zoom_factor - ZOOM_STEP),
+            Action::ResetZoom => self.set_zoom(ctx, 1.0),
+            Action::CommandPalette => {
+                self.command_palette = if self.command_palette.is_some() { None } else { Some(CommandPalette::default()) };
+            }
+            Action::GoHome => self.change_dir(dirs::home_dir().expect("Failed to get home path")),
+            Action::NewFolder => self.apply_background_action(BackgroundAction::NewFolder),
+            Action::NewFile => self.apply_background_action(BackgroundAction::NewFile),
+            Action::OpenTerminal => self.apply_background_action(BackgroundAction::OpenTerminal),
+            Action::OpenSettings => self.show_settings = !self.show_settings,
+            Action::ToggleFolderTree => self.show_tree = !self.show_tree,
+            Action::ToggleVolumes => self.show_volumes = !self.show_volumes,
+            Action::TogglePreview => self.show_preview = !self.show_preview,
+            Action::ToggleViewMode => {
+                self.view_mode = if self.view_mode == ViewMode::List { ViewMode::Grid } else { ViewMode::List };
+            }
+            Action::SortByName | Action::SortBySize | Action::SortByType | Action::SortByModified => {
+                self.sort_column = match action {
+                    Action::SortBySize => SortColumn::Size,
+                    Action::SortByType => SortColumn::Type,
+                    Action::SortByModified => SortColumn::Modified,
+                    _ => SortColumn::Name
+                };
+
+                self.sort_entries();
+            }
+            Action::ReverseSortOrder => {
+                self.sort_ascending = !self.sort_ascending;
+                self.sort_entries();
+            }
+            Action::Properties => {
+                // Like Explorer, only for a single entry.
+                if self.selected_entries.len() <= 1 {
+                    if let Some(entry) = self.selected_entry.and_then(| selected | self.current_dir_items.get(selected)) {
+                        self.properties = Some(PropertiesDialog::new(&entry.path));
+                    }
+                }
+            }
+        }
+    }
+
+    fn elevated_banner(ui: &mut egui::Ui) {
+        egui::Frame::none()
+            .fill(ui.visuals().error_fg_color)
+            .inner_margin(4.0)
+            .rounding(2.0)
+            .show(ui, | ui | {
+                ui.set_width(ui.available_width());
+
+                let text = egui::RichText::new("⚠ Running with elevated privileges").color(egui::Color32::WHITE).strong();
+                ui.label(text).on_hover_text("Deleting or renaming things here can affect system files");
+            })
+        ;
+    }
+
+    // Shortcuts to the usual folders, greyed out if the system doesn't have one of them.
+    fn places_buttons(&mut self, ui: &mut egui::Ui) {
+        let home_hint = match self.keymap.get(&Action::GoHome) {
+            Some(combo) => format!("Home ({})", combo),
+            None => "Home".to_string()
+        };
+
+        let [home, desktop, documents, downloads] = self.places.get_or_insert_with(|| {
+            [dirs::home_dir(), dirs::desktop_dir(), dirs::document_dir(), dirs::download_dir()]
+                .map(| path | path.filter(| path | path.is_dir()))
+        }).clone();
+
+        let places = [
+            ("🏠", home_hint, home),
+            ("🖥", "Desktop".to_string(), desktop),
+            ("🗐", "Documents".to_string(), documents),
+            ("⬇", "Downloads".to_string(), downloads)
+        ];
+
+        for (icon, name, path) in places {
+            let response = ui.add_enabled(path.is_some(), egui::Button::new(icon).small())
+                .on_hover_text(&name)
+                .on_disabled_hover_text(format!("{}: not found on this system", name))
+            ;
+
+            if let Some(path) = path.filter(| _ | response.clicked()) {
+                self.change_dir(path);
+            }
+        }
+    }
+
+    fn command_palette(&mut self, ctx: &egui::Context) {
+        let (picked, close) = match self.command_palette.as_mut() {
+            Some(palette) => palette.show(ctx, &self.keymap),
+            None => return
+        };
+
+        if close {
+            self.command_palette = None;
+        }
+
+        if let Some(action) = picked {
+            self.run_action(ctx, action);
+        }
+    }
+
+    fn set_zoom(&mut self, ctx: &egui::Context, zoom: f32) {
+        self.zoom_factor = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        ctx.set_zoom_factor(self.zoom_factor);
+    }
+
+    // Subfolders of the typed path's parent that start with its last component,
+    // only read again when the typed text changes.
+    fn update_path_suggestions(&mut self) {
+        const MAX_SUGGESTIONS: usize = 10;
+
+        if self.path_suggestions_for == self.current_path_str {
+            return;
+        }
+
+        self.path_suggestions_for = self.current_path_str.clone();
+        self.path_suggestions.clear();
+        self.path_suggestion = None;
+
+        let typed = PathBuf::from(&self.current_path_str);
+
+        // "/home/" lists everything in /home, "/home/us" the folders in /home starting with "us".
+        let (parent, prefix) = {
+            if self.current_path_str.ends_with(std::path::is_separator) {
+                (typed.as_path(), String::new())
+            }
+            else {
+                match (typed.parent(), typed.file_name()) {
+                    (Some(parent), Some(name)) => (parent, name.to_string_lossy().to_lowercase()),
+                    _ => return
+                }
+            }
+        };
+
+        if let Ok(entries) = std::fs::read_dir(parent) {
+            let mut folders: Vec<String> = entries.flatten()
+                .filter(| entry | entry.path().is_dir())
+                .filter(| entry | entry.file_name().to_string_lossy().to_lowercase().starts_with(&prefix))
+                .map(| entry | entry.path().to_string_lossy().to_string())
+                .filter(| path | *path != self.current_path_str)
+                .collect()
+            ;
+
+            folders.sort_by(| a, b | ExplorerApp::natural_cmp(a, b, false));
+            folders.truncate(MAX_SUGGESTIONS);
+
+            self.path_suggestions = folders;
+        }
+    }
+
+    // Only counts the key going down, not the repeats while it's held. Otherwise holding Enter
+    // after typing a path or renaming an entry would go on to open whatever gets selected next.
+    fn enter_pressed(input: &egui::InputState) -> bool {
+        input.events.iter().any(| e | matches!(e, egui::Event::Key { key: egui::Key::Enter, pressed: true, repeat: false, .. }))
+    }
+
+    fn handle_list_keys(&mut self, ctx: &egui::Context) {
+        // Typing in the path box, renaming an entry or picking a new shortcut takes priority over list navigation.
+        if ctx.wants_keyboard_input() || self.renaming_entry.is_some() || self.rebinding_action.is_some() {
+            return;
+        }
+
+        self.type_to_select(ctx);
+
+        let (up, down, enter) = ctx.input(| i | {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                // Alt+Enter is for the properties, not for opening the entry.
+                ExplorerApp::enter_pressed(i) && !i.modifiers.alt
+            )
+        });
+
+        if up || down {
+            let visible_items = self.visible_entries();
+            let current = self.selected_entry.and_then(| selected | visible_items.iter().position(| idx | *idx == selected));
+
+            // The ".." row sits right above the first entry.
+            if self.parent_row_selected {
+                if let Some(first) = visible_items.first().filter(| _ | down) {
+                    self.select_only(*first);
+                    self.scroll_to_selected = true;
+                }
+            }
+            else if up && current == Some(0) && self.has_parent_row() {
+                self.select_parent_row();
+                self.scroll_to_selected = true;
+            }
+            else if !visible_items.is_empty() {
+                let target = match current {
+                    Some(position) if up => position.saturating_sub(1),
+                    Some(position) => (position + 1).min(visible_items.len() - 1),
+                    None => 0
+                };
+
+                self.select_only(visible_items[target]);
+                self.scroll_to_selected = true;
+            }
+        }
+
+        if enter && self.parent_row_selected && self.has_parent_row() {
+            self.previous_level();
+            return;
+        }
+
+        if let Some(selected) = self.selected_entry {
+            if enter {
+                self.open_entries(selected);
+                return;
+            }
+        }
+
+        self.handle_paste(ctx);
+    }
+
+    // Ctrl+V pastes entries that were cut or copied, here or in another file manager. Otherwise, a path pasted
+    // from elsewhere opens its folder, selecting the entry if it points at a file.
+    fn handle_paste(&mut self, ctx: &egui::Context) {
+        let (pasted_text, ctrl_v) = ctx.input(| i | {
+            let pasted_text = i.events.iter().find_map(| e | match e {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None
+            });
+
+            (pasted_text, i.modifiers.command && i.key_pressed(egui::Key::V))
+        });
+
+        if pasted_text.is_none() && !ctrl_v {
+            return;
+        }
+
+        // Whatever was copied elsewhere got picked up when the window was focused.
+        if self.clipboard.is_some() {
+            self.paste_clipboard();
+            return;
+        }
+
+        if let Some(text) = pasted_text {
+            let path = PathBuf::from(text.trim().trim_matches('"'));
+
+            if path.is_dir() {
+                self.change_dir(path);
+            }
+            else if path.exists() {
+                self.reveal(&path);
+            }
+            else {
+                self.set_error(format!("Couldn't find {}", path.display()));
+            }
+        }
+    }
+
+    // Jumps to the first entry starting with what's been typed, case-insensitively.
+    // The typed prefix starts over after a second without input.
+    fn type_to_select(&mut self, ctx: &egui::Context) {
+        const RESET_AFTER: f32 = 1.0;
+
+        let typed: String = ctx.input(| i | {
+            if i.modifiers.command || i.modifiers.alt {
+                return String::new();
+            }
+
+            i.events.iter()
+                .filter_map(| e | match e {
+                    egui::Event::Text(text) => Some(text.as_str()),
+                    _ => None
+                })
+                .collect()
+        });
+
+        let typed = typed.chars().filter(| c | !c.is_control()).collect::<String>().to_lowercase();
+
+        if typed.is_empty() {
+            return;
+        }
+
+        let mut prefix = match self.type_to_select.take() {
+            Some((prefix, typed_at)) if typed_at.elapsed().as_secs_f32() < RESET_AFTER => prefix,
+            _ => String::new()
+        };
+
+        prefix.push_str(&typed);
+
+        let found = self.visible_entries().into_iter().find(| idx | {
+            self.current_dir_items.get(*idx).map(| entry | entry.name.to_lowercase().starts_with(&prefix)).unwrap_or(false)
+        });
+
+        if let Some(idx) = found {
+            self.select_only(idx);
+            self.scroll_to_selected = true;
+        }
+
+        self.type_to_select = Some((prefix, Instant::now()));
+    }
+
+    fn clear_selection(&mut self) {
+        self.selected_entry = None;
+        self.selected_entries.clear();
+        self.parent_row_selected = false;
+    }
+
+    fn has_parent_row(&self) -> bool {
+        self.show_parent_row && self.current_path.parent().is_some()
+    }
+
+    fn select_parent_row(&mut self) {
+        self.clear_selection();
+        self.parent_row_selected = true;
+    }
+
+    // Only entries that pass the filter get selected.
+    fn select_all(&mut self) {
+        let visible_items = self.visible_entries();
+
+        if !self.selected_entry.map(| idx | visible_items.contains(&idx)).unwrap_or(false) {
+            self.selected_entry = visible_items.first().copied();
+        }
+
+        self.selected_entries = visible_items.into_iter().collect();
+    }
+
+    // Entries hidden by the filter end up unselected either way.
+    fn invert_selection(&mut self) {
+        let selected: BTreeSet<usize> = self.visible_entries().into_iter()
+            .filter(| idx | !self.selected_entries.contains(idx))
+            .collect()
+        ;
+
+        self.selected_entry = selected.first().copied();
+        self.selected_entries = selected;
+    }
+
+    fn select_only(&mut self, idx: usize) {
+        self.selected_entry = Some(idx);
+        self.selected_entries = BTreeSet::from([idx]);
+        self.parent_row_selected = false;
+    }
+
+    // Plain clicks select a single entry, Ctrl toggles one in or out of the selection
+    // and Shift selects everything between the last clicked entry and this one.
+    fn click_entry(&mut self, idx: usize, modifiers: egui::Modifiers) {
+        self.parent_row_selected = false;
+
+        if modifiers.shift {
+            if let Some(anchor) = self.selected_entry {
+                let visible_items = self.visible_entries();
+                let anchor_position = visible_items.iter().position(| item | *item == anchor);
+                let clicked_position = visible_items.iter().position(| item | *item == idx);
+
+                if let (Some(anchor_position), Some(clicked_position)) = (anchor_position, clicked_position) {
+                    let start = anchor_position.min(clicked_position);
+                    let end = anchor_position.max(clicked_position);
+
+                    self.selected_entries = visible_items[start..=end].iter().copied().collect();
+                    return;
+                }
+            }
+        }
+
+        if modifiers.command {
+            if !self.selected_entries.remove(&idx) {
+                self.selected_entries.insert(idx);
+            }
+
+            self.selected_entry = Some(idx);
+            return;
+        }
+
+        self.select_only(idx);
+    }
+
+    // The paths an action on `idx` applies to: the whole selection if the entry is part of it,
+    // otherwise just the entry itself.
+    fn action_targets(&self, idx: usize) -> Vec<PathBuf> {
+        let indices = {
+            if self.selected_entries.contains(&idx) {
+                self.selected_entries.iter().copied().collect()
+            }
+            else {
+                vec![idx]
+            }
+        };
+
+        indices.iter()
+            .filter_map(| idx | self.current_dir_items.get(*idx))
+            .map(| entry | entry.path.clone())
+            .collect()
+    }
+
+    fn open_entry(&mut self, idx: usize) {
+        if let Some(entry) = self.current_dir_items.get(idx) {
+            if entry.is_broken_link() {
+                self.set_error(format!("Couldn't open {}: the link target doesn't exist", entry.name));
+            }
+            else if !entry.is_folder() {
+                open::that_in_background(&entry.path);
+            }
+            else if entry._type == EntryType::Symlink && self.follow_symlinks {
+                // Follow the link, so going up a level leads to where the folder actually lives.
+                let target = std::fs::canonicalize(&entry.path).unwrap_or(entry.path.clone());
+                self.change_dir(target);
+            }
+            else {
+                self.change_dir(entry.path.clone());
+            }
+        }
+    }
+
+    // Opens every selected file if `idx` is part of the selection.
+    // Folders are skipped, unless there are no files to open.
+    fn open_entries(&mut self, idx: usize) {
+        if !self.selected_entries.contains(&idx) || self.selected_entries.len() < 2 {
+            self.open_entry(idx);
+            return;
+        }
+
+        let files: Vec<PathBuf> = self.selected_entries.iter()
+            .filter_map(| idx | self.current_dir_items.get(*idx))
+            .filter(| entry | !entry.is_folder() && !entry.is_broken_link())
+            .map(| entry | entry.path.clone())
+            .collect()
+        ;
+
+        if files.is_empty() {
+            self.open_entry(idx);
+        }
+        else if files.len() > MAX_OPEN_WITHOUT_CONFIRMATION {
+            self.pending_open = Some(files);
+        }
+        else {
+            for file in files.iter() {
+                open::that_in_background(file);
+            }
+        }
+    }
+
+    fn open_confirmation_dialog(&mut self, ctx: &egui::Context) {
+        let mut confirmed = None;
+
+        if let Some(files) = self.pending_open.as_ref() {
+            egui::Window::new("Open files")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, | ui | {
+                    ui.label(format!("Open {} files at once?", files.len()));
+                    ui.weak("Each one starts its associated application.");
+
+                    ui.separator();
+
+                    ui.horizontal(| ui | {
+                        if ui.button("Cancel").clicked() {
+                            confirmed = Some(false);
+                        }
+
+                        if ui.button("Open all").clicked() {
+                            confirmed = Some(true);
+                        }
+                    });
+                })
+            ;
+
+            if ctx.input(| i | i.key_pressed(egui::Key::Escape)) {
+                confirmed = Some(false);
+            }
+        }
+
+        if let Some(confirmed) = confirmed {
+            if let Some(files) = self.pending_open.take() {
+                if confirmed {
+                    for file in files.iter() {
+                        open::that_in_background(file);
+                    }
+                }
+            }
+        }
+    }
+
+    fn run_confirmation_dialog(&mut self, ctx: &egui::Context) {
+        let mut confirmed = None;
+
+        if let Some(path) = self.pending_run.as_ref() {
+            egui::Window::new("Run program")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, | ui | {
+                    ui.label(format!("Run {}?", path.file_name().unwrap_or_default().to_string_lossy()));
+                    ui.weak("Only run programs you trust.");
+
+                    ui.separator();
+
+                    ui.horizontal(| ui | {
+                        if ui.button("Cancel").clicked() {
+                            confirmed = Some(false);
+                        }
+
+                        if ui.button("Run").clicked() {
+                            confirmed = Some(true);
+                        }
+                    });
+                })
+            ;
+
+            if ctx.input(| i | i.key_pressed(egui::Key::Escape)) {
+                confirmed = Some(false);
+            }
+        }
+
+        if let Some(confirmed) = confirmed {
+            if let Some(path) = self.pending_run.take() {
+                if confirmed {
+                    let mut command = std::process::Command::new(&path);
+
+                    if let Some(parent) = path.parent() {
+                        command.current_dir(parent);
+                    }
+
+                    if let Err(e) = command.spawn() {
+                        self.set_error(format!("Couldn't run {}: {}", path.display(), e));
+                    }
+                }
+            }
+        }
+    }
+
+    fn sort_menu(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+
+        ui.label("Sort by");
+
+        for column in SortColumn::ALL.into_iter().filter(| column | column.is_available()) {
+            changed |= ui.radio_value(&mut self.sort_column, column, column.label()).changed();
+        }
+
+        ui.separator();
+
+        changed |= ui.radio_value(&mut self.sort_ascending, true, "Ascending").changed();
+        changed |= ui.radio_value(&mut self.sort_ascending, false, "Descending").changed();
+
+        ui.separator();
+
+        changed |= ui.checkbox(&mut self.folders_first, "Folders first").changed();
+
+        if changed {
+            self.sort_entries();
+        }
+    }
+
+    fn startup_dir_menu(&mut self, ui: &mut egui::Ui) {
+        ui.label("Start in");
+        ui.radio_value(&mut self.startup_dir, StartupDir::Home, "Home folder");
+        ui.radio_value(&mut self.startup_dir, StartupDir::LastVisited, "Last visited folder");
+
+        let fixed_label = match &self.startup_dir {
+            StartupDir::Fixed(path) => path.display().to_string(),
+            _ => "A specific folder…".to_string()
+        };
+
+        if ui.radio(matches!(self.startup_dir, StartupDir::Fixed(_)), fixed_label).clicked() {
+            let folder = rfd::FileDialog::new()
+                .set_title("Start in")
+                .set_directory(&self.current_path)
+                .pick_folder()
+            ;
+
+            if let Some(folder) = folder {
+                self.startup_dir = StartupDir::Fixed(folder);
+            }
+        }
+    }
+
+    // Show/hide toggles for the list columns, with buttons to move the visible ones around.
+    fn columns_menu(&mut self, ui: &mut egui::Ui) {
+        let mut toggled = None;
+        let mut swapped = None;
+
+        ui.label("Columns");
+
+        for (position, column) in self.columns.iter().enumerate() {
+            ui.horizontal(| ui | {
+                let mut visible = true;
+
+                ui.add_enabled_ui(*column != SortColumn::Name, | ui | {
+                    if ui.checkbox(&mut visible, column.label()).changed() {
+                        toggled = Some(*column);
+                    }
+                });
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), | ui | {
+                    if ui.add_enabled(position + 1 < self.columns.len(), egui::Button::new("⏷").small()).clicked() {
+                        swapped = Some((position, position + 1));
+                    }
+
+                    if ui.add_enabled(position > 0, egui::Button::new("⏶").small()).clicked() {
+                        swapped = Some((position, position - 1));
+                    }
+                });
+            });
+        }
+
+        for column in SortColumn::ALL.iter().filter(| column | column.is_available() && !self.columns.contains(column)) {
+            let mut visible = false;
+
+            if ui.checkbox(&mut visible, column.label()).changed() {
+                toggled = Some(*column);
+            }
+        }
+
+        if let Some(column) = toggled {
+            if let Some(position) = self.columns.iter().position(| c | *c == column) {
+                self.columns.remove(position);
+            }
+            else {
+                self.columns.push(column);
+            }
+        }
+
+        if let Some((a, b)) = swapped {
+            self.columns.swap(a, b);
+        }
+    }
+
+    fn settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .vscroll(true)
+            .default_width(360.0)
+            .show(ctx, | ui | {
+                egui::CollapsingHeader::new("Appearance").default_open(true).show(ui, | ui | {
+                    ui.label("Theme");
+                    ui.radio_value(&mut self.theme, Theme::System, "Follow the system");
+                    ui.radio_value(&mut self.theme, Theme::Light, "Light");
+                    ui.radio_value(&mut self.theme, Theme::Dark, "Dark");
+
+                    ui.separator();
+
+                    ui.label("Show times as");
+                    ui.radio_value(&mut self.time_display, TimeDisplay::Relative, "Relative (3 days ago)");
+                    ui.radio_value(&mut self.time_display, TimeDisplay::Absolute, "Absolute (2024-01-15 14:32)");
+
+                    ui.separator();
+
+                    ui.add(egui::Slider::new(&mut self.grid_cell_size, 48.0..=192.0).text("Grid icon size"));
+                    ui.add(egui::Slider::new(&mut self.row_padding, 0.0..=24.0).text("Row padding"));
+                    ui.add(egui::Slider::new(&mut self.recent_change_minutes, 0..=120).suffix(" min").text("Mark recently changed files"))
+                        .on_hover_text("0 turns it off")
+                    ;
+
+                    if ui.add(egui::Slider::new(&mut self.zoom_factor, MIN_ZOOM..=MAX_ZOOM).text("Zoom")).changed() {
+                        ctx.set_zoom_factor(self.zoom_factor);
+                    }
+
+                    ui.separator();
+
+                    ui.checkbox(&mut self.show_parent_row, "Show a \"..\" row to go up a level");
+
+                    ui.separator();
+
+                    self.columns_menu(ui);
+                });
+
+                egui::CollapsingHeader::new("Behavior").default_open(true).show(ui, | ui | {
+                    self.startup_dir_menu(ui);
+
+                    ui.separator();
+
+                    ui.checkbox(&mut self.open_folders_in_new_tab, "Open folders in a new tab (Ctrl+double-click to stay)");
+                    ui.checkbox(&mut self.follow_symlinks, "Go to where linked folders really are when opening them");
+                    ui.checkbox(&mut self.remember_history, "Remember back and forward history between sessions");
+                    ui.checkbox(&mut self.permanent_delete, "Delete permanently instead of using the trash");
+                    ui.checkbox(&mut self.confirm_cross_fs_moves, "Ask before moving files to another drive");
+                });
+
+                egui::CollapsingHeader::new("Files").default_open(true).show(ui, | ui | {
+                    ui.checkbox(&mut self.binary_units, "Show sizes in binary units (KiB, MiB)");
+
+                    // Either way the current directory keeps the order it's shown in right now.
+                    if ui.checkbox(&mut self.sort_per_directory, "Remember the sort order of each folder").changed() && self.sort_per_directory {
+                        self.save_view_state();
+                    }
+
+                    let mut resort = false;
+
+                    resort |= ui.checkbox(&mut self.folders_first, "Show folders first").changed();
+                    resort |= ui.checkbox(&mut self.size_sort_folders_first, "Put folders first when sorting by size, until their size is known").changed();
+                    resort |= ui.checkbox(&mut self.natural_sort, "Sort numbers in names by value").changed();
+                    resort |= ui.checkbox(&mut self.case_sensitive, "Case-sensitive sorting and filtering").changed();
+
+                    if resort {
+                        self.sort_entries();
+                    }
+
+                    ui.add(egui::Slider::new(&mut self.large_dir_threshold, 1_000..=500_000).logarithmic(true).text("Entries loaded at once in big folders"));
+
+                    if cfg!(windows) && ui.checkbox(&mut self.hide_system_files, "Hide protected operating system files").changed() {
+                        self.reload_dir_entries();
+                    }
+
+                    ui.separator();
+
+                    ui.label("Name colors");
+                    self.name_colors_menu(ui);
+                });
+
+                egui::CollapsingHeader::new("Quick filters").default_open(true).show(ui, | ui | {
+                    self.saved_filters_menu(ui);
+                });
+
+                egui::CollapsingHeader::new("Keyboard shortcuts").show(ui, | ui | {
+                    self.keymap_menu(ui);
+                });
+            })
+        ;
+
+        self.show_settings = open;
+    }
+
+    fn recent_dirs_menu(&mut self, ui: &mut egui::Ui) {
+        let mut target = None;
+
+        // Folders could have been removed since they were visited.
+        self.recent_dirs.retain(| path | path.is_dir());
+
+        if self.recent_dirs.is_empty() {
+            ui.weak("No recent locations");
+        }
+
+        for path in self.recent_dirs.iter() {
+            let response = ui.selectable_label(*path == self.current_path, ExplorerApp::tab_title(path))
+                .on_hover_text(path.display().to_string())
+            ;
+
+            if response.clicked() {
+                target = Some(path.clone());
+                ui.close_menu();
+            }
+        }
+
+        if let Some(path) = target {
+            if path != self.current_path {
+                self.change_dir(path);
+            }
+        }
+    }
+
+    fn name_colors_menu(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+
+        let mut extensions: Vec<String> = self.name_colors.keys().cloned().collect();
+        extensions.sort();
+
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, | ui | {
+            egui::Grid::new("name_colors").num_columns(3).show(ui, | ui | {
+                for extension in extensions.iter() {
+                    if let Some(color) = self.name_colors.get_mut(extension) {
+                        ui.color_edit_button_srgba(color);
+                        ui.colored_label(*color, format!(".{}", extension));
+
+                        if ui.small_button("✖").clicked() {
+                            removed = Some(extension.clone());
+                        }
+
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+
+        if let Some(extension) = removed {
+            self.name_colors.remove(&extension);
+        }
+
+        ui.separator();
+
+        ui.horizontal(| ui | {
+            ui.add(egui::TextEdit::singleline(&mut self.new_color_extension).hint_text("Extension").desired_width(80.0));
+
+            let extension = self.new_color_extension.trim().trim_start_matches('.').to_lowercase();
+
+            if ui.add_enabled(!extension.is_empty(), egui::Button::new("Add")).clicked() {
+                self.name_colors.entry(extension).or_insert(ui.visuals().text_color());
+                self.new_color_extension.clear();
+            }
+        });
+
+        if ui.button("Restore defaults").clicked() {
+            self.name_colors = default_name_colors();
+        }
+    }
+
+    fn keymap_menu(&mut self, ui: &mut egui::Ui) {
+        if let Some(action) = self.rebinding_action {
+            let pressed = ui.input(| i | {
+                i.events.iter().find_map(| e | match e {
+                    egui::Event::Key { key, pressed: true, modifiers, .. } => Some(KeyCombo::new(*modifiers, *key)),
+                    _ => None
+                })
+            });
+
+            if let Some(combo) = pressed {
+                self.keymap.insert(action, combo);
+                self.rebinding_action = None;
+            }
+        }
+
+        egui::Grid::new("keymap").num_columns(3).striped(true).show(ui, | ui | {
+            for action in Action::ALL {
+                ui.label(action.label());
+
+                if self.rebinding_action == Some(action) {
+                    if ui.button("Press a key… (click to cancel)").clicked() {
+                        self.rebinding_action = None;
+                    }
+                }
+                else {
+                    let combo = self.keymap.get(&action).map(| combo | combo.to_string()).unwrap_or_else(|| "Not set".to_string());
+
+                    if ui.button(combo).on_hover_text("Click to change").clicked() {
+                        self.rebinding_action = Some(action);
+                    }
+                }
+
+                let conflicts = keymap::conflicts(&self.keymap, action);
+
+                if !conflicts.is_empty() {
+                    let names: Vec<&str> = conflicts.iter().map(| other | other.label()).collect();
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ Also used for {}", names.join(", ")));
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if ui.button("Restore defaults").clicked() {
+            self.keymap = keymap::default_keymap();
+            self.rebinding_action = None;
+        }
+    }
+
+    fn saved_filters_menu(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+
+        if self.saved_filters.is_empty() {
+            ui.weak("Quick filters show up next to the built-in categories");
+        }
+
+        egui::Grid::new("saved_filters").num_columns(3).show(ui, | ui | {
+            for (idx, filter) in self.saved_filters.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut filter.name).hint_text("Name").desired_width(100.0));
+                ui.add(egui::TextEdit::singleline(&mut filter.patterns).hint_text("jpg, png, image/").desired_width(180.0));
+
+                if ui.small_button("✖").clicked() {
+                    removed = Some(idx);
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some(idx) = removed {
+            self.saved_filters.remove(idx);
+        }
+
+        if ui.button("Add filter").clicked() {
+            self.saved_filters.push(file_types::SavedFilter {
+                name: "New filter".to_string(),
+                patterns: String::new(),
+                active: false
+            });
+        }
+    }
+
+    fn tab_bar(&mut self, ui: &mut egui::Ui) {
+        let mut switch_to = None;
+        let mut close = None;
+        let mut open_new = false;
+
+        egui::ScrollArea::horizontal().id_source("tab_bar").show(ui, | ui | {
+            ui.horizontal(| ui | {
+                for idx in 0..self.tabs.len() {
+                    let title = {
+                        if idx == self.active_tab {
+                            ExplorerApp::tab_title(&self.current_path)
+                        }
+                        else {
+                            ExplorerApp::tab_title(&self.tabs[idx].current_path)
+                        }
+                    };
+
+                    ui.push_id(idx, | ui | {
+                        if ui.selectable_label(idx == self.active_tab, title).clicked() {
+                            switch_to = Some(idx);
+                        }
+
+                        if self.tabs.len() > 1 && ui.small_button("x").clicked() {
+                            close = Some(idx);
+                        }
+                    });
+
+                    ui.separator();
+                }
+
+                if ui.small_button("+").clicked() {
+                    open_new = true;
+                }
+            });
+        });
+
+        if let Some(idx) = close {
+            self.close_tab(idx);
+        }
+        else if let Some(idx) = switch_to {
+            self.switch_tab(idx);
+        }
+
+        if open_new {
+            self.open_tab(dirs::home_dir().expect("Failed to get home path"));
+        }
+    }
+
+    fn set_error(&mut self, msg: String) {
+        self.status_message = Some((msg, Instant::now(), true));
+    }
+
+    // For things that went fine, shown in the normal text color.
+    fn set_status(&mut self, msg: String) {
+        self.status_message = Some((msg, Instant::now(), false));
+    }
+
+    fn status_bar(&mut self, ctx: &egui::Context) {
+        const MESSAGE_DURATION: f32 = 5.0;
+        const FADE_DURATION: f32 = 1.0;
+
+        let message_elapsed = self.status_message.as_ref().map(| (_, shown_at, _) | shown_at.elapsed().as_secs_f32());
+
+        if message_elapsed.map(| elapsed | elapsed >= MESSAGE_DURATION).unwrap_or(false) {
+            self.status_message = None;
+        }
+
+        egui::TopBottomPanel::bottom("status_message").show(ctx, | ui | {
+            ui.horizontal(| ui | {
+                if let (Some((message, _, is_error)), Some(elapsed)) = (self.status_message.as_ref(), message_elapsed) {
+                    // Fully visible at first, then fades out over the last second.
+                    let opacity = ((MESSAGE_DURATION - elapsed) / FADE_DURATION).min(1.0);
+                    let color = if *is_error { ui.visuals().error_fg_color } else { ui.visuals().text_color() };
+                    let color = color.gamma_multiply(opacity);
+
+                    ui.colored_label(color, message);
+                    ctx.request_repaint();
+                }
+
+                if let Some(task) = self.archive_task.as_ref() {
+                    ui.label(task.description());
+                    ui.add(egui::ProgressBar::new(task.progress()).desired_width(150.0).show_percentage());
+                }
+
+                // Big transfers get their own dialog instead.
+                if let Some(task) = self.transfer_task.as_ref().filter(| task | !task.is_large()) {
+                    let verb = if task.mode() == ClipboardMode::Copy { "Copying" } else { "Moving" };
+
+                    ui.spinner();
+                    ui.label(format!("{} {} items…", verb, task.count()));
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), | ui | {
+                    if let Some((available, total)) = self.disk_space {
+                        let used = total.saturating_sub(available) as f32 / total.max(1) as f32;
+
+                        ui.add(egui::ProgressBar::new(used).desired_width(80.0).desired_height(6.0));
+                        ui.label(format!("{} free of {}", ExplorerApp::size_to_string(available as usize, self.binary_units), ExplorerApp::size_to_string(total as usize, self.binary_units)));
+                    }
+
+                    if !self.selected_entries.is_empty() {
+                        let size: u64 = self.selected_entries.iter()
+                            .filter_map(| idx | self.current_dir_items.get(*idx))
+                            .map(| entry | entry.computed_size.unwrap_or(entry.length as u64))
+                            .sum()
+                        ;
+
+                        ui.separator();
+                        ui.label(format!("{} selected, {} total", self.selected_entries.len(), ExplorerApp::size_to_string(size as usize, self.binary_units)));
+                    }
+
+                    if !self.loading {
+                        let shown = self.visible_entries().len();
+                        let total = self.current_dir_items.len();
+
+                        ui.separator();
+
+                        if shown == total {
+                            ui.label(format!("{} items", total));
+                        }
+                        else {
+                            ui.label(format!("{} items ({} shown)", total, shown));
+                        }
+                    }
+                });
+            });
+        });
+    }
+
+    fn delete_confirmation_dialog(&mut self, ctx: &egui::Context) {
+        let mut confirmed = None;
+
+        if let Some(paths) = self.pending_delete.as_ref() {
+            let name = {
+                if let [path] = paths.as_slice() {
+                    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+                }
+                else {
+                    format!("{} items", paths.len())
+                }
+            };
+
+            egui::Window::new("Confirm deletion")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, | ui | {
+                    if self.pending_delete_permanent {
+                        ui.label(format!("Delete {}? This cannot be undone.", name));
+                    }
+                    else {
+                        ui.label(format!("Move {} to the trash?", name));
+                    }
+
+                    if let Some(count) = self.pending_delete_count {
+                        ui.label(format!("This folder contains {} items.", count));
+                    }
+
+                    if let Some(error) = self.pending_delete_error.as_ref() {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("Couldn't move it to the trash: {}", error));
+                    }
+
+                    ui.separator();
+
+                    ui.horizontal(| ui | {
+                        if ui.button("Cancel").clicked() {
+                            confirmed = Some(false);
+                        }
+
+                        let delete_label = if self.pending_delete_permanent { "Delete" } else { "Move to trash" };
+
+                        if ui.button(delete_label).clicked() {
+                            confirmed = Some(true);
+                        }
+                    });
+                })
+            ;
+
+            if ctx.input(| i | i.key_pressed(egui::Key::Escape)) {
+                confirmed = Some(false);
+            }
+        }
+
+        if let Some(confirmed) = confirmed {
+            if let Some(paths) = self.pending_delete.take() {
+                if confirmed {
+                    if self.pending_delete_permanent {
+                        for path in paths.iter() {
+                            if let Err(e) = fs_utils::remove_entry(path) {
+                                self.set_error(format!("Couldn't delete {}: {}", path.display(), e));
+                            }
+                        }
+
+                        // Older operations could depend on what was just deleted.
+                        self.undo_stack.clear();
+                    }
+                    else if let Err(e) = trash::delete_all(&paths) {
+                        // Keep the dialog up with the reason, offering a permanent deletion instead.
+                        self.pending_delete = Some(paths);
+                        self.pending_delete_permanent = true;
+                        self.pending_delete_error = Some(e.to_string());
+
+                        return;
+                    }
+                    else if undo::can_restore_trash() {
+                        self.push_undo(FileOp::Trash(paths));
+                    }
+                    else {
+                        self.undo_stack.clear();
+                    }
+
+                    self.refresh_dir();
+                }
+            }
+
+            self.pending_delete_count = None;
+            self.pending_delete_error = None;
+        }
+    }
+
+    fn fill_files_table(&mut self, ui: &mut egui::Ui) {
+        let text_size = egui::TextStyle::Body.resolve(ui.style()).size + self.row_padding;
+        let mut rename_finished = None;
+        let mut clicked_column = None;
+        let mut toggled_folders_first = false;
+        let mut entry_action = None;
+        let mut clicked_entry = None;
+        let mut dropped_entry = None;
+        let mut renamed = None;
+        let mut error_message = None;
+
+        let visible_items = self.visible_entries();
+        let time_display = self.time_display;
+        let binary_units = self.binary_units;
+        let can_paste = self.clipboard.is_some();
+        let multiple_selected = self.selected_entries.len() > 1;
+        let open_with = &self.open_with;
+        let name_colors = &self.name_colors;
+        let open_folders_in_new_tab = self.open_folders_in_new_tab;
+        let recent_change_minutes = self.recent_change_minutes;
+        let mut first_visible_row = None;
+
+        // Nothing else repaints an idle window, and "5 seconds ago" shouldn't stay that way.
+        let shows_times = self.columns.iter().any(| column | matches!(column, SortColumn::Created | SortColumn::Accessed | SortColumn::Modified));
+
+        if time_display == TimeDisplay::Relative && shows_times {
+            ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+        }
+
+        // The ".." row takes the first row, entries start after it.
+        let parent_row = self.has_parent_row();
+        let row_offset = if parent_row { 1 } else { 0 };
+        let parent_row_selected = self.parent_row_selected;
+        let mut parent_row_clicked = false;
+        let mut parent_row_opened = false;
+
+        let columns = self.columns.clone();
+
+        let mut table = TableBuilder::new(ui)
+            .resizable(true)
+            .striped(true)
+        ;
+
+        for (position, column) in columns.iter().enumerate() {
+            // The last column takes whatever space is left.
+            if position == columns.len() - 1 {
+                table = table.column(egui_extras::Column::remainder());
+            }
+            else {
+                let width = self.column_widths.get(column).copied().unwrap_or(column.initial_width());
+                table = table.column(egui_extras::Column::initial(width));
+            }
+        }
+
+        if let Some(row) = self.pending_scroll_row.take() {
+            table = table.scroll_to_row(row, Some(egui::Align::TOP));
+        }
+
+        if self.scroll_to_selected {
+            self.scroll_to_selected = false;
+
+            let selected_row = {
+                if self.parent_row_selected && parent_row {
+                    Some(0)
+                }
+                else {
+                    self.selected_entry
+                        .and_then(| selected | visible_items.iter().position(| idx | *idx == selected))
+                        .map(| row | row + row_offset)
+                }
+            };
+
+            if let Some(row) = selected_row {
+                table = table.scroll_to_row(row, None);
+            }
+        }
+
+        let mut header_widths = Vec::new();
+
+        table
+            .header(20.0, | mut header | {
+                for column in columns.iter().copied() {
+                    let (rect, _) = header.col(| ui | {
+                        let is_active = self.sort_column == column;
+                        let label = {
+                            if is_active {
+                                format!("{} {}", column.label(), if self.sort_ascending { "▲" } else { "▼" })
+                            }
+                            else {
+                                column.label().to_string()
+                            }
+                        };
+
+                        let header_label = ui.selectable_label(is_active, egui::RichText::new(label).strong());
+
+                        if header_label.clicked() {
+                            clicked_column = Some(column);
+                        }
+
+                        header_label.context_menu(| ui | {
+                            if ui.selectable_label(self.folders_first, "Folders first").clicked() {
+                                toggled_folders_first = true;
+                                ui.close_menu();
+                            }
+                        });
+                    });
+
+                    header_widths.push((column, rect.width()));
+                }
+            })
+            .body(| body | {
+                body.rows(text_size, visible_items.len() + row_offset, | mut row | {
+                    // Only the rows on screen get laid out, so the first one we see is the top of the view.
+                    if first_visible_row.is_none() {
+                        first_visible_row = Some(row.index());
+                    }
+
+                    // Shown in whichever column comes first, so it's there even with the Name column hidden.
+                    if parent_row && row.index() == 0 {
+                        for (idx, _) in columns.iter().enumerate() {
+                            row.col(| ui | {
+                                if idx > 0 {
+                                    return;
+                                }
+
+                                let response = ui.selectable_label(parent_row_selected, "🗁 ..").on_hover_text("Go up a level");
+
+                                if response.double_clicked() {
+                                    parent_row_opened = true;
+                                }
+                                else if response.clicked() {
+                                    parent_row_clicked = true;
+                                }
+                            });
+                        }
+
+                        return;
+                    }
+
+                    let row_idx = visible_items[row.index() - row_offset];
+
+                    if let Some(entry) = self.current_dir_items.get(row_idx) {
+                        for column in columns.iter() {
+                            row.col(| ui | {
+                                match column {
+                                    SortColumn::Name => {
+                                        let renaming = {
+                                            if let Some(target) = self.renaming_entry.as_ref() {
+                                                row_idx == *target
+                                            }
+                                            else {
+                                                false
+                                            }
+                                        };
+
+                                        if renaming {
+                                            if let Some(result) = ExplorerApp::rename_editor(ui, entry, &mut self.renaming_string) {
+                                                match result {
+                                                    Ok(Some(op)) => {
+                                                        renamed = Some(op);
+                                                        rename_finished = Some(entry.path.clone());
+                                                    }
+                                                    // Cancelled, or the name was left as it was.
+                                                    Ok(None) => {}
+                                                    Err(message) => error_message = Some(message)
+                                                }
+
+                                                self.renaming_entry = None;
+                                                self.renaming_string = String::new();
+                                            }
+                                        }
+                                        else {
+                                            let is_selected = self.selected_entries.contains(&row_idx);
+                                            let name_color = ExplorerApp::name_color(name_colors, entry);
+                                
+                                            let entry_label = {
+                                                ui.push_id(&entry.path, | ui | {
+                                                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                                        let thumbnail = {
+                                                            if entry._type == EntryType::File && ThumbnailCache::is_supported(&entry.path) {
+                                                                self.thumbnails.get(ui.ctx(), &entry.path, entry.modified_at)
+                                                            }
+                                                            else {
+                                                                None
+                                                            }
+                                                        };
+
+                                                        // Images show a thumbnail in place of the icon once it's been decoded.
+                                                        let mut label = match thumbnail {
+                                                            Some(texture) => {
+                                                                let size = ui.text_style_height(&egui::TextStyle::Button);
+                                                                ui.add(egui::Image::new(&texture).fit_to_exact_size(egui::vec2(size, size)));
+
+                                                                egui::RichText::new(entry.display_name())
+                                                            }
+                                                            None => egui::RichText::new(format!("{} {}", icon_for(entry), entry.display_name()))
+                                                        };
+
+                                                        // Broken links are muted, there's nothing to open.
+                                                        if entry.is_broken_link() {
+                                                            label = label.italics().weak();
+                                                        }
+                                                        // The selection keeps its own text color so it stays readable.
+                                                        else if let Some(color) = name_color.filter(| _ | !is_selected) {
+                                                            label = label.color(color);
+                                                        }
+
+                                                        if ExplorerApp::is_recently_changed(ui.ctx(), entry, recent_change_minutes) {
+                                                            ui.label(egui::RichText::new("●").small().color(ui.visuals().hyperlink_color))
+                                                                .on_hover_text(format!("Changed in the last {} minutes", recent_change_minutes))
+                                                            ;
+                                                        }
+
+                                                        let max_width = ui.available_width() - ui.spacing().button_padding.x * 2.0;
+                                                        let galley = ExplorerApp::elided_galley(ui, label, max_width);
+                                                        let mut response = ui.selectable_label(is_selected, galley)
+                                                            .on_hover_ui(| ui | ExplorerApp::entry_tooltip(ui, entry, binary_units))
+                                                        ;
+
+                                                        if entry.has_invalid_name() {
+                                                            response = response.on_hover_text("This name isn't valid text, some characters can't be shown");
+                                                        }
+
+                                                        response
+                                                    }).inner
+                                                }).inner
+                                            };
+
+                                            let entry_label = entry_label.interact(egui::Sense::drag());
+
+                                            if let Some(drop) = ExplorerApp::entry_drag_and_drop(ui, &entry_label, entry, row_idx) {
+                                                dropped_entry = Some(drop);
+                                            }
+                
+                                            if entry_label.double_clicked() {
+                                                let modifiers = ui.input(| i | i.modifiers);
+                                                entry_action = Some((row_idx, ExplorerApp::double_click_action(entry, modifiers, open_folders_in_new_tab)));
+                                                clicked_entry = Some((row_idx, egui::Modifiers::NONE));
+                                            }
+                                            else if entry_label.clicked() {
+                                                clicked_entry = Some((row_idx, ui.input(| i | i.modifiers)));
+                                            }
+                                            else if entry_label.middle_clicked() && entry.is_folder() {
+                                                entry_action = Some((row_idx, ExplorerApp::middle_click_action(ui.input(| i | i.modifiers))));
+                                            }
+                
+                                            entry_label.context_menu(| ui | {
+                                                if let Some(action) = ExplorerApp::entry_context_menu(ui, entry, can_paste, is_selected && multiple_selected, ExplorerApp::open_with_apps(open_with, entry)) {
+                                                    entry_action = Some((row_idx, action));
+                                                }
+                                            });
+                                        }
+                                    }
+                                    SortColumn::Type => {
+                                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                            ui.label(&entry.type_name);
+                                        });
+                                    }
+                                    SortColumn::Size => {
+                                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                            if entry._type == EntryType::Folder {
+                                                match entry.computed_size {
+                                                    Some(size) => ui.label(ExplorerApp::size_to_string(size as usize, binary_units)),
+                                                    None => ui.weak("…")
+                                                };
+                                            }
+                                            else {
+                                                ui.label(ExplorerApp::size_to_string(entry.length, binary_units)); 
+                                            }
+                                        });
+                                    }
+                                    SortColumn::Created => {
+                                        if let Some(creation_time) = ExplorerApp::time_to_string(time_display, entry.created_at.as_ref()) {
+                                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                                ui.label(creation_time);
+                                            });
+                                        }
+                                    }
+                                    SortColumn::Accessed => {
+                                        if let Some(last_accessed) = ExplorerApp::time_to_string(time_display, entry.accessed_at.as_ref()) {
+                                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                                ui.label(last_accessed);
+                                            });
+                                        }
+                                    }
+                                    SortColumn::Modified => {
+                                        if let Some(last_modified) = ExplorerApp::time_to_string(time_display, entry.modified_at.as_ref()) {
+                                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                                ui.label(last_modified);
+                                            });
+                                        }
+                                    }
+                                    SortColumn::Permissions => {
+                                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                            ui.label(&entry.permissions); 
+                                        });
+                                    }
+                                    SortColumn::Owner | SortColumn::Group => {
+                                        let name = if *column == SortColumn::Owner { entry.owner.as_ref() } else { entry.group.as_ref() };
+
+                                        if let Some(name) = name {
+                                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                                                ui.label(name);
+                                            });
+                                        }
+                                    }
+                                }
+                            });
+                        }
+                    }
+                });
+            })
+        ;
+
+        if let Some(row) = first_visible_row {
+            self.scroll_row = row;
+        }
+
+        let (background_action, go_up) = self.background_area(ui);
+
+        if let Some(message) = error_message {
+            self.set_error(message);
+        }
+
+        // The entry moves around when its new name sorts differently, keep it selected.
+        let rename_finished = rename_finished.map(| original | match renamed.as_ref() {
+            Some(FileOp::Rename { to, .. }) => to.clone(),
+            _ => original
+        });
+
+        if let Some(op) = renamed {
+            self.push_undo(op);
+        }
+
+        if let Some((idx, modifiers)) = clicked_entry {
+            self.click_entry(idx, modifiers);
+        }
+
+        if let Some((idx, action)) = entry_action {
+            self.apply_entry_action(ui.ctx(), idx, action);
+        }
+
+        if let Some((dragged, target)) = dropped_entry {
+            self.drop_entries(dragged, target);
+        }
+
+        if let Some(action) = background_action {
+            self.apply_background_action(action);
+        }
+
+        if let Some(column) = clicked_column {
+            if self.sort_column == column {
+                self.sort_ascending = !self.sort_ascending;
+            }
+            else {
+                self.sort_column = column;
+                self.sort_ascending = true;
+            }
+
+            self.sort_entries();
+        }
+
+        // The last column fills the remaining space, its width isn't the user's choice.
+        header_widths.pop();
+
+        for (column, width) in header_widths {
+            self.column_widths.insert(column, width);
+        }
+
+        if toggled_folders_first {
+            self.folders_first = !self.folders_first;
+            self.sort_entries();
+        }
+
+        if parent_row_clicked {
+            self.select_parent_row();
+        }
+
+        if let Some(path) = rename_finished {
+            self.refresh_dir();
+            self.pending_select = Some(path);
+        }
+        else if go_up || parent_row_opened {
+            self.previous_level();
+        }
+    }
+
+    fn fill_files_grid(&mut self, ui: &mut egui::Ui) {
+        let cell_size = self.grid_cell_size;
+        let name_font = egui::TextStyle::Body.resolve(ui.style());
+        // Room for two lines of the name under the icon.
+        let cell = egui::vec2(cell_size, cell_size + name_font.size * 2.0 + 8.0);
+
+        let mut rename_finished = None;
+        let mut entry_action = None;
+        let mut clicked_entry = None;
+        let mut dropped_entry = None;
+        let mut renamed = None;
+        let mut error_message = None;
+
+        let visible_items = self.visible_entries();
+        let can_paste = self.clipboard.is_some();
+        let multiple_selected = self.selected_entries.len() > 1;
+        let open_with = &self.open_with;
+        let name_colors = &self.name_colors;
+        let open_folders_in_new_tab = self.open_folders_in_new_tab;
+        let recent_change_minutes = self.recent_change_minutes;
+        let scroll_to_selected = std::mem::take(&mut self.scroll_to_selected);
+        let parent_row = self.has_parent_row();
+        let parent_row_selected = self.parent_row_selected;
+        let mut parent_row_clicked = false;
+        let mut parent_row_opened = false;
+
+        ui.horizontal_wrapped(| ui | {
+            if parent_row {
+                let response = ExplorerApp::parent_grid_cell(ui, cell, &name_font, parent_row_selected);
+
+                if response.double_clicked() {
+                    parent_row_opened = true;
+                }
+                else if response.clicked() {
+                    parent_row_clicked = true;
+                }
+
+                if scroll_to_selected && parent_row_selected {
+                    response.scroll_to_me(None);
+                }
+            }
+
+            for idx in visible_items {
+                if let Some(entry) = self.current_dir_items.get(idx) {
+                    if self.renaming_entry == Some(idx) {
+                        ui.allocate_ui(cell, | ui | {
+                            ui.vertical_centered(| ui | {
+                                ui.label(egui::RichText::new(icon_for(entry)).size(cell_size * 0.5));
+
+                                if let Some(result) = ExplorerApp::rename_editor(ui, entry, &mut self.renaming_string) {
+                                    match result {
+                                        Ok(Some(op)) => {
+                                            renamed = Some(op);
+                                            rename_finished = Some(entry.path.clone());
+                                        }
+                                        // Cancelled, or the name was left as it was.
+                                        Ok(None) => {}
+                                        Err(message) => error_message = Some(message)
+                                    }
+
+                                    self.renaming_entry = None;
+                                    self.renaming_string = String::new();
+                                }
+                            });
+                        });
+
+                        continue;
+                    }
+
+                    let is_selected = self.selected_entries.contains(&idx);
+                    let (rect, response) = ui.allocate_exact_size(cell, egui::Sense::click_and_drag());
+
+                    let visuals = ui.visuals();
+                    let text_color = {
+                        if is_selected {
+                            visuals.selection.stroke.color
+                        }
+                        else if entry.is_broken_link() {
+                            visuals.weak_text_color()
+                        }
+                        else {
+                            ExplorerApp::name_color(name_colors, entry).unwrap_or(visuals.text_color())
+                        }
+                    };
+
+                    if is_selected {
+                        ui.painter().rect_filled(rect, 4.0, visuals.selection.bg_fill);
+                    }
+                    else if response.hovered() {
+                        ui.painter().rect_filled(rect, 4.0, visuals.widgets.hovered.weak_bg_fill);
+                    }
+
+                    let painter = ui.painter_at(rect);
+
+                    painter.text(
+                        rect.center_top() + egui::vec2(0.0, cell_size / 2.0),
+                        egui::Align2::CENTER_CENTER,
+                        icon_for(entry),
+                        egui::FontId::proportional(cell_size * 0.5),
+                        text_color
+                    );
+
+                    let name = painter.layout(entry.name.clone(), name_font.clone(), text_color, cell_size - 4.0);
+                    let name_pos = egui::pos2(rect.center().x - name.size().x / 2.0, rect.top() + cell_size);
+
+                    painter.galley(name_pos, name, text_color);
+
+                    if ExplorerApp::is_recently_changed(ui.ctx(), entry, recent_change_minutes) {
+                        painter.circle_filled(rect.right_top() + egui::vec2(-8.0, 8.0), 3.0, ui.visuals().hyperlink_color);
+                    }
+
+                    let response = response.on_hover_text(entry.display_name());
+
+                    if let Some(drop) = ExplorerApp::entry_drag_and_drop(ui, &response, entry, idx) {
+                        dropped_entry = Some(drop);
+                    }
+
+                    if response.double_clicked() {
+                        let modifiers = ui.input(| i | i.modifiers);
+                        entry_action = Some((idx, ExplorerApp::double_click_action(entry, modifiers, open_folders_in_new_tab)));
+                        clicked_entry = Some((idx, egui::Modifiers::NONE));
+                    }
+                    else if response.clicked() {
+                        clicked_entry = Some((idx, ui.input(| i | i.modifiers)));
+                    }
+                    else if response.middle_clicked() && entry.is_folder() {
+                        entry_action = Some((idx, ExplorerApp::middle_click_action(ui.input(| i | i.modifiers))));
+                    }
+
+                    if scroll_to_selected && is_selected {
+                        response.scroll_to_me(None);
+                    }
+
+                    response.context_menu(| ui | {
+                        if let Some(action) = ExplorerApp::entry_context_menu(ui, entry, can_paste, is_selected && multiple_selected, ExplorerApp::open_with_apps(open_with, entry)) {
+                            entry_action = Some((idx, action));
+                        }
+                    });
+                }
+            }
+        });
+
+        let (background_action, go_up) = self.background_area(ui);
+
+        if let Some(message) = error_message {
+            self.set_error(message);
+        }
+
+        // The entry moves around when its new name sorts differently, keep it selected.
+        let rename_finished = rename_finished.map(| original | match renamed.as_ref() {
+            Some(FileOp::Rename { to, .. }) => to.clone(),
+            _ => original
+        });
+
+        if let Some(op) = renamed {
+            self.push_undo(op);
+        }
+
+        if let Some((idx, modifiers)) = clicked_entry {
+            self.click_entry(idx, modifiers);
+        }
+
+        if let Some((idx, action)) = entry_action {
+            self.apply_entry_action(ui.ctx(), idx, action);
+        }
+
+        if let Some((dragged, target)) = dropped_entry {
+            self.drop_entries(dragged, target);
+        }
+
+        if let Some(action) = background_action {
+            self.apply_background_action(action);
+        }
+
+        if parent_row_clicked {
+            self.select_parent_row();
+        }
+
+        if let Some(path) = rename_finished {
+            self.refresh_dir();
+            self.pending_select = Some(path);
+        }
+        else if go_up || parent_row_opened {
+            self.previous_level();
+        }
+    }
+
+    // The ".." cell leading the grid, drawn like a folder's.
+    fn parent_grid_cell(ui: &mut egui::Ui, cell: egui::Vec2, name_font: &egui::FontId, selected: bool) -> egui::Response {
+        let cell_size = cell.x;
+        let (rect, response) = ui.allocate_exact_size(cell, egui::Sense::click());
+
+        let visuals = ui.visuals();
+        let text_color = if selected { visuals.selection.stroke.color } else { visuals.text_color() };
+
+        if selected {
+            ui.painter().rect_filled(rect, 4.0, visuals.selection.bg_fill);
+        }
+        else if response.hovered() {
+            ui.painter().rect_filled(rect, 4.0, visuals.widgets.hovered.weak_bg_fill);
+        }
+
+        let painter = ui.painter_at(rect);
+
+        painter.text(
+            rect.center_top() + egui::vec2(0.0, cell_size / 2.0),
+            egui::Align2::CENTER_CENTER,
+            "🗁",
+            egui::FontId::proportional(cell_size * 0.5),
+            text_color
+        );
+
+        painter.text(
+            egui::pos2(rect.center().x, rect.top() + cell_size),
+            egui::Align2::CENTER_TOP,
+            "..",
+            name_font.clone(),
+            text_color
+        );
+
+        response.on_hover_text("Go up a level")
+    }
+
+    // Makes an entry draggable, and folders a place to drop them on. Returns the dragged
+    // and target entries once something gets dropped.
+    fn entry_drag_and_drop(ui: &egui::Ui, response: &egui::Response, entry: &EntryInfo, idx: usize) -> Option<(usize, usize)> {
+        response.dnd_set_drag_payload(DraggedEntry(idx));
+
+        if !entry.is_folder() {
+            return None;
+        }
+
+        if let Some(dragged) = response.dnd_hover_payload::<DraggedEntry>() {
+            if dragged.0 != idx {
+                ui.painter().rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+            }
+        }
+
+        match response.dnd_release_payload::<DraggedEntry>() {
+            Some(dragged) if dragged.0 != idx => Some((dragged.0, idx)),
+            _ => None
+        }
+    }
+
+    // Moves the dragged entry, or the whole selection if it's part of it, into the target folder.
+    fn drop_entries(&mut self, dragged: usize, target: usize) {
+        let target_path = match self.current_dir_items.get(target) {
+            Some(entry) => entry.path.clone(),
+            None => return
+        };
+
+        let sources: Vec<PathBuf> = self.action_targets(dragged).into_iter()
+            .filter(| source | *source != target_path)
+            .collect()
+        ;
+
+        // The folder can be a mount point. Copying everything over can take long enough to need the progress dialog.
+        if sources.iter().any(| source | !fs_utils::same_filesystem(source, &target_path)) {
+            self.start_transfer(sources, target_path, ClipboardMode::Cut);
+            return;
+        }
+
+        let mut moved = Vec::new();
+
+        for source in sources {
+            match fs_utils::move_into(&source, &target_path) {
+                Ok(target) => moved.push((source, target)),
+                Err(e) => self.set_error(format!("Couldn't move {}: {}", source.display(), e))
+            }
+        }
+
+        if !moved.is_empty() {
+            self.push_undo(FileOp::Move(moved));
+        }
+
+        self.refresh_dir();
+    }
+
+    // Lays out `text` on a single line, cutting it short with an ellipsis if it doesn't fit in `max_width`.
+    fn elided_galley(ui: &egui::Ui, text: egui::RichText, max_width: f32) -> Arc<egui::Galley> {
+        let mut job = egui::text::LayoutJob::default();
+        text.append_to(&mut job, ui.style(), egui::FontSelection::Style(egui::TextStyle::Button), egui::Align::Center);
+
+        job.wrap = egui::text::TextWrapping {
+            max_width,
+            max_rows: 1,
+            break_anywhere: true,
+            overflow_character: Some('…')
+        };
+
+        ui.fonts(| fonts | fonts.layout_job(job))
+    }
+
+    // Empty space below the entries, for directory-wide actions.
+    // Returns the action picked from its context menu, and whether the space was double-clicked.
+    fn background_area(&mut self, ui: &mut egui::Ui) -> (Option<BackgroundAction>, bool) {
+        let mut action = None;
+        let background = ui.interact(ui.available_rect_before_wrap(), ui.id().with("background"), egui::Sense::click());
+
+        background.context_menu(| ui | {
+            ui.add_enabled_ui(self.clipboard.is_some(), |ui| {
+                if ui.selectable_label(false, "Paste").clicked() {
+                    action = Some(BackgroundAction::Paste);
+                }
+
+                if ui.selectable_label(false, "Paste as link").clicked() {
+                    action = Some(BackgroundAction::PasteAsLink);
+                }
+            });
+
+            ui.separator();
+
+            if ui.selectable_label(false, "New folder").clicked() {
+                action = Some(BackgroundAction::NewFolder);
+            }
+
+            if ui.selectable_label(false, "New file").clicked() {
+                action = Some(BackgroundAction::NewFile);
+            }
+
+            ui.separator();
+
+            if ui.selectable_label(false, "Open terminal here").clicked() {
+                action = Some(BackgroundAction::OpenTerminal);
+            }
+
+            if ui.selectable_label(false, "Refresh").clicked() {
+                action = Some(BackgroundAction::Refresh);
+            }
+
+            ui.separator();
+
+            if ui.selectable_label(false, "Select all").clicked() {
+                action = Some(BackgroundAction::SelectAll);
+            }
+
+            if ui.selectable_label(false, "Invert selection").clicked() {
+                action = Some(BackgroundAction::InvertSelection);
+            }
+
+            ui.add_enabled_ui(!self.selected_entries.is_empty(), | ui | {
+                if ui.selectable_label(false, "Clear selection").clicked() {
+                    action = Some(BackgroundAction::ClearSelection);
+                }
+            });
+
+            ui.separator();
+
+            ui.menu_button("Sort by", | ui | {
+                self.sort_menu(ui);
+            });
+
+            ui.menu_button("View", | ui | {
+                ui.radio_value(&mut self.view_mode, ViewMode::List, "List");
+                ui.radio_value(&mut self.view_mode, ViewMode::Grid, "Grid");
+
+                ui.separator();
+
+                ui.checkbox(&mut self.show_volumes, "Volumes");
+                ui.checkbox(&mut self.show_tree, "Folder tree");
+                ui.checkbox(&mut self.show_preview, "Preview pane");
+            });
+
+            if action.is_some() {
+                ui.close_menu();
+            }
+        });
+
+        // Double-clicking the empty space goes up a level, like most file managers do.
+        let go_up = background.double_clicked() && self.renaming_entry.is_none();
+
+        (action, go_up)
+    }
+
+    fn apply_background_action(&mut self, action: BackgroundAction) {
+        match action {
+            BackgroundAction::Paste => self.paste_clipboard(),
+            BackgroundAction::PasteAsLink => self.paste_clipboard_as_links(),
+            BackgroundAction::NewFolder | BackgroundAction::NewFile => {
+                let is_folder = action == BackgroundAction::NewFolder;
+                let path = fs_utils::numbered_path(&self.current_path, if is_folder { "New folder" } else { "New file" });
+
+                let result = {
+                    if is_folder {
+                        std::fs::create_dir(&path)
+                    }
+                    else {
+                        std::fs::OpenOptions::new().write(true).create_new(true).open(&path).map(| _ | ())
+                    }
+                };
+
+                match result {
+                    Ok(_) => {
+                        self.push_undo(FileOp::Create(vec![path.clone()]));
+                        self.refresh_dir();
+
+                        // Let the user name it right away.
+                        self.pending_select = Some(path);
+                        self.pending_rename = true;
+                    }
+                    Err(e) => self.set_error(format!("Couldn't create {}: {}", path.display(), e))
+                }
+            }
+            BackgroundAction::OpenTerminal => {
+                if let Err(e) = terminal::open(&self.current_path) {
+                    self.set_error(format!("Couldn't open a terminal: {}", e));
+                }
+            }
+            BackgroundAction::Refresh => self.refresh_dir(),
+            BackgroundAction::SelectAll => self.select_all(),
+            BackgroundAction::InvertSelection => self.invert_selection(),
+            BackgroundAction::ClearSelection => self.clear_selection()
+        }
+    }
+
+    // Inline text field used to rename an entry. Returns None while the user is still typing,
+    // and the outcome of the rename once the field loses focus, with the operation to undo it.
+    fn rename_editor(ui: &mut egui::Ui, entry: &EntryInfo, renaming_string: &mut String) -> Option<Result<Option<FileOp>, String>> {
+        let mut result = None;
+
+        // Red highlight for the text if there is a file with the same name.
+        if entry.name != *renaming_string {
+            if let Some(path) = entry.path.parent() {
+                if path.join(PathBuf::from(&renaming_string)).exists() {
+                    ui.visuals_mut().override_text_color = Some(ui.visuals().error_fg_color);
+                }
+            }
+        }
+
+        let entry_label = {
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
+                ui.text_edit_singleline(renaming_string)
+            }).response
+        };
+
+        if entry_label.lost_focus() {
+            result = Some(Ok(None));
+
+            // Escape takes the focus away from the text box too, but means leaving the name alone.
+            if ui.input(| i | i.key_pressed(egui::Key::Escape)) {
+                *renaming_string = entry.name.clone();
+            }
+            // User committed the changes. Leaving the name untouched is a no-op, renaming an invalid
+            // name to what's displayed would lose the characters that couldn't be shown.
+            else if ui.input(| i | i.key_pressed(egui::Key::Enter)) && entry.name != *renaming_string {
+                // Check if an entry with the same name already exists.
+                if let Some(parent) = entry.path.parent() {
+                    let new_entry = parent.join(PathBuf::from(&renaming_string));
+                
+                    // There's already an entry on this directory with that name, don't rename.
+                    if !new_entry.exists() {
+                        match std::fs::rename(&entry.path, &new_entry) {
+                            Ok(_) => result = Some(Ok(Some(FileOp::Rename { from: entry.path.clone(), to: new_entry }))),
+                            Err(e) => result = Some(Err(format!("Couldn't rename {}: {}", entry.name, e)))
+                        }
+                    }
+                    else {
+                        result = Some(Err(format!("Couldn't rename {}: {} already exists", entry.name, renaming_string)));
+                    }
+                }
+            }
+        }
+        else {
+            entry_label.request_focus();
+        }
+
+        ui.visuals_mut().override_text_color = None;
+
+        result
+    }
+
+    // Details about a row, so they can be seen with the columns hidden or too narrow.
+    // Only what was already read is shown, hovering shouldn't touch the disk.
+    fn entry_tooltip(ui: &mut egui::Ui, entry: &EntryInfo, binary_units: bool) {
+        ui.strong(entry.display_name());
+        ui.label(entry.path.display().to_string());
+        ui.separator();
+
+        egui::Grid::new("entry_tooltip").num_columns(2).show(ui, | ui | {
+            ui.weak("Type");
+            ui.label(&entry.type_name);
+            ui.end_row();
+
+            let size = {
+                if entry._type == EntryType::Folder {
+                    entry.computed_size.map(| size | ExplorerApp::size_to_string(size as usize, binary_units))
+                }
+                else {
+                    Some(ExplorerApp::size_to_string(entry.length, binary_units))
+                }
+            };
+
+            if let Some(size) = size {
+                ui.weak("Size");
+                ui.label(size);
+                ui.end_row();
+            }
+
+            let times = [
+                ("Modified", entry.modified_at.as_ref()),
+                ("Created", entry.created_at.as_ref()),
+                ("Accessed", entry.accessed_at.as_ref())
+            ];
+
+            for (label, time) in times {
+                if let Some(time) = time {
+                    ui.weak(label);
+                    ui.label(ExplorerApp::system_time_to_string(time));
+                    ui.end_row();
+                }
+            }
+        });
+    }
+
+    // What double-clicking an entry does. Ctrl flips where folders get opened.
+    fn double_click_action(entry: &EntryInfo, modifiers: egui::Modifiers, open_folders_in_new_tab: bool) -> EntryAction {
+        if entry.is_folder() && modifiers.command != open_folders_in_new_tab {
+            EntryAction::OpenInNewTab
+        }
+        else {
+            EntryAction::Open
+        }
+    }
+
+    // Middle-clicking a folder opens it in a new window, or in a new tab while holding Ctrl.
+    fn middle_click_action(modifiers: egui::Modifiers) -> EntryAction {
+        if modifiers.command {
+            EntryAction::OpenInNewTab
+        }
+        else {
+            EntryAction::OpenInNewWindow
+        }
+    }
+
+    // The right-click menu of an entry, shared by the list and grid views.
+    fn entry_context_menu(ui: &mut egui::Ui, entry: &EntryInfo, can_paste: bool, multiple_selected: bool, open_with: &[PathBuf]) -> Option<EntryAction> {
+        let mut action = None;
+
+        if ui.selectable_label(false, "Open").clicked() {
+            action = Some(EntryAction::Open);
+        }
+
+        if entry.executable && ui.selectable_label(false, "Run").clicked() {
+            action = Some(EntryAction::Run);
+        }
+
+        if !entry.is_folder() {
+            ui.menu_button("Open with…", | ui | {
+                for (idx, application) in open_with.iter().enumerate() {
+                    let name = application.file_name().unwrap_or(application.as_os_str()).to_string_lossy().to_string();
+
+                    if ui.selectable_label(false, name).on_hover_text(application.display().to_string()).clicked() {
+                        action = Some(EntryAction::OpenWith(idx));
+                    }
+                }
+
+                if !open_with.is_empty() {
+                    ui.separator();
+                }
+
+                if ui.selectable_label(false, "Choose application…").clicked() {
+                    action = Some(EntryAction::ChooseApplication);
+                }
+            });
+        }
+
+        if entry.is_folder() && ui.selectable_label(false, "Open in new tab").clicked() {
+            action = Some(EntryAction::OpenInNewTab);
+        }
+
+        if entry._type == EntryType::Folder {
+            if ui.selectable_label(false, "Open in new window").clicked() {
+                action = Some(EntryAction::OpenInNewWindow);
+            }
+        }
+
+        if entry._type == EntryType::Symlink && !entry.is_broken_link() && ui.selectable_label(false, "Show link target in folder").clicked() {
+            action = Some(EntryAction::ShowLinkTarget);
+        }
+
+        ui.separator();
+
+        if ui.selectable_label(false, "Cut").clicked() {
+            action = Some(EntryAction::Cut);
+        }
+
+        if ui.selectable_label(false, "Copy").clicked() {
+            action = Some(EntryAction::Copy);
+        }
+
+        ui.add_enabled_ui(can_paste, |ui| {
+            if ui.selectable_label(false, "Paste").clicked() {
+                action = Some(EntryAction::Paste);
+            }
+
+            if ui.selectable_label(false, "Paste as link").clicked() {
+                action = Some(EntryAction::PasteAsLink);
+            }
+        });
+
+        if ui.selectable_label(false, "Duplicate").clicked() {
+            action = Some(EntryAction::Duplicate);
+        }
+
+        if ui.selectable_label(false, "Create link").clicked() {
+            action = Some(EntryAction::CreateLink);
+        }
+
+        if ui.selectable_label(false, "Copy to…").clicked() {
+            action = Some(EntryAction::CopyTo);
+        }
+
+        if ui.selectable_label(false, "Move to…").clicked() {
+            action = Some(EntryAction::MoveTo);
+        }
+
+        ui.separator();
+
+        if ui.selectable_label(false, "Copy path").clicked() {
+            action = Some(EntryAction::CopyPath);
+        }
+
+        if multiple_selected {
+            if ui.selectable_label(false, "Copy as path list").clicked() {
+                action = Some(EntryAction::CopyPathList);
+            }
+
+            if ui.selectable_label(false, "Copy as quoted paths").clicked() {
+                action = Some(EntryAction::CopyQuotedPaths);
+            }
+        }
+
+        if ui.selectable_label(false, "Copy name").clicked() {
+            action = Some(EntryAction::CopyName);
+        }
+
+        ui.separator();
+
+        if ui.selectable_label(false, "Compress to .zip").clicked() {
+            action = Some(EntryAction::Compress);
+        }
+
+        if entry._type == EntryType::File && entry.extension.eq_ignore_ascii_case("zip") {
+            if ui.selectable_label(false, "Extract here").clicked() {
+                action = Some(EntryAction::Extract);
+            }
+        }
+
+        ui.separator();
+
+        if ui.selectable_label(false, "Rename").clicked() {
+            action = Some(EntryAction::Rename);
+        }
+
+        if ui.selectable_label(false, "Remove").clicked() {
+            action = Some(EntryAction::Remove);
+        }
+
+        ui.separator();
+
+        if ui.selectable_label(false, "Properties").clicked() {
+            action = Some(EntryAction::Properties);
+        }
+
+        if action.is_some() {
+            ui.close_menu();
+        }
+
+        action
+    }
+
+    fn apply_entry_action(&mut self, ctx: &egui::Context, idx: usize, action: EntryAction) {
+        let (path, name, entry_type) = match self.current_dir_items.get(idx) {
+            Some(entry) => (entry.path.clone(), entry.name.clone(), entry._type),
+            None => return
+        };
+
+        match action {
+            EntryAction::Open => self.open_entries(idx),
+            EntryAction::Run => self.pending_run = Some(path),
+            EntryAction::OpenWith(app_idx) => {
+                let extension = self.current_dir_items[idx].extension.to_lowercase();
+                let application = self.open_with.get(&extension).and_then(| apps | apps.get(app_idx)).cloned();
+
+                if let Some(application) = application {
+                    self.open_with_application(&extension, application, &path);
+                }
+            }
+            EntryAction::ChooseApplication => {
+                let extension = self.current_dir_items[idx].extension.to_lowercase();
+                let application = rfd::FileDialog::new()
+                    .set_title(format!("Open {} with", name))
+                    .pick_file()
+                ;
+
+                if let Some(application) = application {
+                    self.open_with_application(&extension, application, &path);
+                }
+            }
+            EntryAction::OpenInNewTab => self.open_tab(path),
+            EntryAction::OpenInNewWindow => {
+                // Windows opened from other windows need ids that can't clash with ours.
+                let id = egui::ViewportId::from_hash_of((ctx.viewport_id(), self.windows_opened));
+                self.windows_opened += 1;
+
+                let window = self.new_window(path);
+                self.child_windows.insert(id, window);
+            }
+            EntryAction::ShowLinkTarget => {
+                match std::fs::canonicalize(&path) {
+                    Ok(target) => self.reveal(&target),
+                    Err(e) => self.set_error(format!("Couldn't resolve {}: {}", name, e))
+                }
+            }
+            EntryAction::Cut => self.set_clipboard(self.action_targets(idx), ClipboardMode::Cut),
+            EntryAction::Copy => self.set_clipboard(self.action_targets(idx), ClipboardMode::Copy),
+            EntryAction::Paste => self.paste_clipboard(),
+            EntryAction::PasteAsLink => self.paste_clipboard_as_links(),
+            EntryAction::CreateLink => self.create_links(self.action_targets(idx)),
+            // Copying into the same folder gets the copies a "(copy)" suffix.
+            EntryAction::Duplicate => self.start_transfer(self.action_targets(idx), self.current_path.clone(), ClipboardMode::Copy),
+            EntryAction::CopyTo | EntryAction::MoveTo => {
+                let (title, mode) = {
+                    if action == EntryAction::CopyTo {
+                        ("Copy to", ClipboardMode::Copy)
+                    }
+                    else {
+                        ("Move to", ClipboardMode::Cut)
+                    }
+                };
+
+                let folder = rfd::FileDialog::new()
+                    .set_title(title)
+                    .set_directory(&self.current_path)
+                    .pick_folder()
+                ;
+
+                if let Some(folder) = folder {
+                    self.start_transfer(self.action_targets(idx), folder, mode);
+                }
+            }
+            EntryAction::CopyPath => ctx.output_mut(| o | o.copied_text = path.to_string_lossy().to_string()),
+            EntryAction::CopyPathList | EntryAction::CopyQuotedPaths => {
+                let text = ExplorerApp::path_list(&self.action_targets(idx), action == EntryAction::CopyQuotedPaths);
+                ctx.output_mut(| o | o.copied_text = text);
+            }
+            EntryAction::CopyName => ctx.output_mut(| o | o.copied_text = name),
+            EntryAction::Compress | EntryAction::Extract => {
+                if self.archive_task.is_some() {
+                    self.set_error("Wait for the current archive operation to finish first".to_string());
+                }
+                else if action == EntryAction::Compress {
+                    self.archive_task = Some(ArchiveTask::compress(self.action_targets(idx), &self.current_path));
+                }
+                else {
+                    self.archive_task = Some(ArchiveTask::extract(&path, &self.current_path));
+                }
+            }
+            EntryAction::Rename => {
+                self.renaming_entry = Some(idx);
+                self.renaming_string = name;
+            }
+            EntryAction::Remove => {
+                let targets = self.action_targets(idx);
+
+                self.pending_delete_permanent = self.permanent_delete || ctx.input(| i | i.modifiers.shift);
+                self.pending_delete_error = None;
+                self.pending_delete_count = {
+                    if targets.len() == 1 && entry_type == EntryType::Folder {
+                        std::fs::read_dir(&path).ok().map(| entries | entries.count())
+                    }
+                    else {
+                        None
+                    }
+                };
+                self.pending_delete = Some(targets);
+            }
+            EntryAction::Properties => self.properties = Some(PropertiesDialog::new(&path))
+        }
+    }
+
+    // One path per line, optionally quoted so they can be pasted straight into a shell.
+    fn path_list(paths: &[PathBuf], quoted: bool) -> String {
+        let line_ending = if cfg!(windows) { "\r\n" } else { "\n" };
+
+        paths.iter()
+            .map(| path | {
+                let path = path.to_string_lossy();
+
+                if !quoted {
+                    path.to_string()
+                }
+                else if cfg!(windows) {
+                    // Paths can't contain double quotes on Windows, so there's nothing to escape.
+                    format!("\"{}\"", path)
+                }
+                else {
+                    format!("'{}'", path.replace('\'', "'\\''"))
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(line_ending)
+    }
+
+    fn open_with_apps<'a>(open_with: &'a HashMap<String, Vec<PathBuf>>, entry: &EntryInfo) -> &'a [PathBuf] {
+        open_with.get(&entry.extension.to_lowercase()).map(| apps | apps.as_slice()).unwrap_or_default()
+    }
+
+    // Launches `application` with the file as its argument, and remembers it for the extension.
+    fn open_with_application(&mut self, extension: &str, application: PathBuf, file: &Path) {
+        if let Err(e) = std::process::Command::new(&application).arg(file).spawn() {
+            self.set_error(format!("Couldn't start {}: {}", application.display(), e));
+            return;
+        }
+
+        let apps = self.open_with.entry(extension.to_string()).or_default();
+        apps.retain(| app | *app != application);
+        apps.insert(0, application);
+    }
+
+    // Indices into `current_dir_items` of the entries that match the current filter.
+    fn visible_entries(&self) -> Vec<usize> {
+        let query = if self.case_sensitive { self.filter_query.clone() } else { self.filter_query.to_lowercase() };
+        let saved_filters: Vec<&file_types::SavedFilter> = self.saved_filters.iter().filter(| filter | filter.active).collect();
+
+        self.current_dir_items.iter()
+            .enumerate()
+            .filter(| (_, entry) | {
+                query.is_empty()
+                    || (self.case_sensitive && entry.name.contains(&query))
+                    || (!self.case_sensitive && entry.name.to_lowercase().contains(&query))
+            })
+            .filter(| (_, entry) | {
+                (self.category_filters.is_empty() && saved_filters.is_empty())
+                    || entry.is_folder()
+                    || entry.category.map(| c | self.category_filters.contains(&c)).unwrap_or(false)
+                    || saved_filters.iter().any(| filter | filter.matches(&entry.extension, entry.media_type.as_deref()))
+            })
+            .map(| (idx, _) | idx)
+            .collect()
+    }
+
+    // Whether the entry was modified in the last `minutes`. Also makes sure the mark goes away on its own.
+    fn is_recently_changed(ctx: &egui::Context, entry: &EntryInfo, minutes: u64) -> bool {
+        let window = std::time::Duration::from_secs(minutes * 60);
+        let age = entry.modified_at.and_then(| modified | SystemTime::now().duration_since(modified).ok());
+
+        match age {
+            Some(age) if age < window => {
+                ctx.request_repaint_after(window - age);
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn name_color(name_colors: &HashMap<String, egui::Color32>, entry: &EntryInfo) -> Option<egui::Color32> {
+        if entry.is_folder() {
+            return None;
+        }
+
+        name_colors.get(&entry.extension.to_lowercase()).copied()
+    }
+
+    // Other file managers can paste the entries too, as long as the system clipboard can be reached.
+    fn set_clipboard(&mut self, paths: Vec<PathBuf>, mode: ClipboardMode) {
+        self.clipboard_exported = file_clipboard::system().set(&paths, mode).is_ok();
+        // A read that was still going would bring back whatever was there before.
+        self.clipboard_receiver = None;
+        self.clipboard = Some((paths, mode));
+    }
+
+    // Picks up files copied in other programs. Replaces any read that's still going,
+    // in case the program owning the clipboard stopped answering.
+    fn import_system_clipboard(&mut self, ctx: &egui::Context) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            if sender.send(file_clipboard::system().get()).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        self.clipboard_receiver = Some(receiver);
+    }
+
+    fn poll_system_clipboard(&mut self) {
+        let contents = match self.clipboard_receiver.as_ref().map(| receiver | receiver.try_recv()) {
+            Some(Ok(contents)) => contents,
+            Some(Err(TryRecvError::Empty)) | None => return,
+            Some(Err(TryRecvError::Disconnected)) => {
+                self.clipboard_receiver = None;
+                return;
+            }
+        };
+
+        self.clipboard_receiver = None;
+
+        match contents {
+            Some((paths, mode)) => {
+                // Cut files that were pasted already aren't there anymore.
+                let paths: Vec<PathBuf> = paths.into_iter().filter(| path | path.exists()).collect();
+
+                self.clipboard = if paths.is_empty() { None } else { Some((paths, mode)) };
+                self.clipboard_exported = true;
+            }
+            // Something else got copied since, so pasting our entries would be a surprise.
+            None if self.clipboard_exported => {
+                self.clipboard = None;
+                self.clipboard_exported = false;
+            }
+            None => {}
+        }
+    }
+
+    fn paste_clipboard(&mut self) {
+        if self.transfer_task.is_some() {
+            self.set_error("Wait for the current transfer to finish before pasting".to_string());
+            return;
+        }
+
+        if let Some((paths, mode)) = self.clipboard.take() {
+            // Cut entries are gone from their original location, so they can only be pasted once.
+            if mode == ClipboardMode::Copy {
+                self.clipboard = Some((paths.clone(), mode));
+            }
+
+            self.start_transfer(paths, self.current_path.clone(), mode);
+        }
+    }
+
+    // The clipboard is left alone, even for cut entries, since they stay where they are.
+    fn paste_clipboard_as_links(&mut self) {
+        if let Some((paths, _)) = self.clipboard.clone() {
+            self.create_links(paths);
+        }
+    }
+
+    // Creates links to `targets` in the current directory.
+    fn create_links(&mut self, targets: Vec<PathBuf>) {
+        let mut created = Vec::new();
+
+        for target in targets {
+            let link = fs_utils::link_path(&self.current_path, &target);
+
+            if let Err(e) = fs_utils::create_symlink(&target, &link) {
+                // ERROR_PRIVILEGE_NOT_HELD, Windows only lets admins create links unless Developer Mode is on.
+                if cfg!(windows) && e.raw_os_error() == Some(1314) {
+                    self.set_error("Creating links on Windows needs administrator rights or Developer Mode".to_string());
+                }
+                else {
+                    self.set_error(format!("Couldn't create a link to {}: {}", target.display(), e));
+                }
+
+                break;
+            }
+
+            created.push(link);
+        }
+
+        if created.is_empty() {
+            return;
+        }
+
+        if created.len() == 1 {
+            self.pending_select = created.first().cloned();
+        }
+
+        self.push_undo(FileOp::Create(created));
+        self.refresh_dir();
+    }
+
+    fn start_transfer(&mut self, paths: Vec<PathBuf>, dest_dir: PathBuf, mode: ClipboardMode) {
+        if self.transfer_task.is_some() {
+            self.set_error("Wait for the current transfer to finish".to_string());
+            return;
+        }
+
+        if mode == ClipboardMode::Cut && self.confirm_cross_fs_moves && paths.iter().any(| path | !fs_utils::same_filesystem(path, &dest_dir)) {
+            self.pending_cross_fs_move = Some((paths, dest_dir));
+            return;
+        }
+
+        self.transfer_task = Some(TransferTask::new(paths, &dest_dir, mode));
+    }
+
+    fn cross_fs_move_dialog(&mut self, ctx: &egui::Context) {
+        let mut confirmed = None;
+
+        if let Some((paths, dest_dir)) = self.pending_cross_fs_move.as_ref() {
+            egui::Window::new("Move to another drive")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, | ui | {
+                    ui.label(format!("{} is on a different drive.", dest_dir.display()));
+                    ui.label(format!("Moving {} items there copies them first, and only removes the originals once the copy is done.", paths.len()));
+                    ui.weak("This can take a while for big files.");
+
+                    ui.separator();
+
+                    ui.checkbox(&mut self.confirm_cross_fs_moves, "Ask every time");
+
+                    ui.horizontal(| ui | {
+                        if ui.button("Cancel").clicked() {
+                            confirmed = Some(false);
+                        }
+
+                        if ui.button("Move").clicked() {
+                            confirmed = Some(true);
+                        }
+                    });
+                })
+            ;
+
+            if ctx.input(| i | i.key_pressed(egui::Key::Escape)) {
+                confirmed = Some(false);
+            }
+        }
+
+        if let Some(confirmed) = confirmed {
+            if let Some((paths, dest_dir)) = self.pending_cross_fs_move.take() {
+                if confirmed {
+                    self.transfer_task = Some(TransferTask::new(paths, &dest_dir, ClipboardMode::Cut));
+                }
+            }
+        }
+    }
+
+    // Keeps the recursive search in sync with the filter box and the current directory.
+    fn update_search(&mut self, ctx: &egui::Context) {
+        if !self.recursive_search || self.filter_query.is_empty() {
+            self.search_task = None;
+            return;
+        }
+
+        let outdated = self.search_task.as_ref()
+            .map(| task | task.root() != self.current_path || task.query() != self.filter_query || task.is_case_sensitive() != self.case_sensitive)
+            .unwrap_or(true)
+        ;
+
+        if outdated {
+            self.search_task = Some(SearchTask::new(ctx, &self.current_path, &self.filter_query, self.case_sensitive));
+        }
+
+        if let Some(task) = self.search_task.as_mut() {
+            task.poll();
+        }
+    }
+
+    fn search_results(&mut self, ui: &mut egui::Ui) {
+        let mut target = None;
+        let mut opened = None;
+
+        if let Some(task) = self.search_task.as_ref() {
+            ui.horizontal(| ui | {
+                if task.is_finished() {
+                    ui.label(format!("{} matches", task.matches().len()));
+                }
+                else {
+                    ui.spinner();
+                    ui.label(format!("Searching… {} matches", task.matches().len()));
+
+                    if ui.button("Cancel").clicked() {
+                        task.cancel();
+                    }
+                }
+
+                if task.is_truncated() {
+                    ui.weak("(stopped at the first results, try a longer query)");
+                }
+            });
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, | ui | {
+                for found in task.matches() {
+                    let name = found.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    let icon = if found.is_folder { "🗁" } else { "🗋" };
+
+                    ui.horizontal(| ui | {
+                        let response = ui.selectable_label(false, format!("{} {}", icon, name));
+
+                        if response.clicked() {
+                            target = Some(found.path.clone());
+                        }
+
+                        response.context_menu(| ui | {
+                            if ui.selectable_label(false, "Open").clicked() {
+                                opened = Some(found.path.clone());
+                                ui.close_menu();
+                            }
+
+                            if ui.selectable_label(false, "Open containing folder").clicked() {
+                                target = Some(found.path.clone());
+                                ui.close_menu();
+                            }
+                        });
+
+                        // Where it is, relative to the folder the search started from.
+                        if let Some(parent) = found.path.parent().and_then(| parent | parent.strip_prefix(task.root()).ok()) {
+                            if !parent.as_os_str().is_empty() {
+                                ui.weak(parent.display().to_string());
+                            }
+                        }
+                    });
+                }
+            });
+        }
+
+        // Leaving the directory clears the query, which ends the search.
+        if let Some(path) = target {
+            self.reveal(&path);
+        }
+
+        if let Some(path) = opened {
+            if path.is_dir() {
+                self.change_dir(path);
+            }
+            else {
+                open::that_in_background(path);
+            }
+        }
+    }
+
+    fn poll_volumes(&mut self, ctx: &egui::Context) {
+        const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+        if let Some(receiver) = self.volumes_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(volumes) => {
+                    self.volumes = volumes;
+                    self.volumes_receiver = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.volumes_receiver = None
+            }
+        }
+
+        if !self.show_volumes || self.volumes_receiver.is_some() {
+            return;
+        }
+
+        if self.volumes_listed_at.map(| listed_at | listed_at.elapsed() >= REFRESH_INTERVAL).unwrap_or(true) {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let ctx = ctx.clone();
+
+            std::thread::spawn(move || {
+                if sender.send(volumes::list()).is_ok() {
+                    ctx.request_repaint();
+                }
+            });
+
+            self.volumes_receiver = Some(receiver);
+            self.volumes_listed_at = Some(Instant::now());
+        }
+
+        ctx.request_repaint_after(REFRESH_INTERVAL);
+    }
+
+    fn volumes_sidebar(&mut self, ui: &mut egui::Ui) {
+        let mut target = None;
+
+        // The volume the current directory is on is the one with the longest matching root.
+        let current = self.volumes.iter()
+            .filter(| volume | self.current_path.starts_with(&volume.root))
+            .max_by_key(| volume | volume.root.as_os_str().len())
+            .map(| volume | volume.root.clone())
+        ;
+
+        ui.strong("Volumes");
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, | ui | {
+            for volume in self.volumes.iter() {
+                let is_current = current.as_ref() == Some(&volume.root);
+
+                let response = ui.selectable_label(is_current, format!("🖴 {}", volume.label))
+                    .on_hover_text(volume.root.display().to_string())
+                ;
+
+                if response.clicked() {
+                    target = Some(volume.root.clone());
+                }
+
+                if let Some((available, total)) = volume.space {
+                    ui.weak(format!("{} free of {}", ExplorerApp::size_to_string(available as usize, self.binary_units), ExplorerApp::size_to_string(total as usize, self.binary_units)));
+                }
+
+                ui.add_space(4.0);
+            }
+        });
+
+        if let Some(target) = target {
+            self.change_dir(target);
+        }
+    }
+
+    fn poll_transfer_task(&mut self, ctx: &egui::Context) {
+        let result = match self.transfer_task.as_mut().map(| task | task.poll()) {
+            Some(Some(result)) => result,
+            Some(None) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return;
+            }
+            None => return
+        };
+
+        let (mode, count, dest_dir) = match self.transfer_task.take() {
+            Some(task) => (task.mode(), task.count(), task.dest_dir().to_path_buf()),
+            None => return
+        };
+
+        let (verb, done) = if mode == ClipboardMode::Copy { ("copy", "Copied") } else { ("move", "Moved") };
+        let completed = result.completed.len();
+
+        if let Some(e) = result.error {
+            self.set_error(format!("Couldn't {} {}", verb, e));
+        }
+        else if result.cancelled {
+            self.set_status(format!("Cancelled, {} of {} items done", completed, count));
+        }
+        else {
+            let noun = if completed == 1 { "item" } else { "items" };
+            self.set_status(format!("{} {} {}", done, completed, noun));
+        }
+
+        // A single new entry is most likely what the user wants to work with next.
+        if let [(_, target)] = result.completed.as_slice() {
+            if dest_dir == self.current_path {
+                self.pending_select = Some(target.clone());
+            }
+        }
+
+        if completed > 0 {
+            if mode == ClipboardMode::Copy {
+                self.push_undo(FileOp::Create(result.completed.into_iter().map(| (_, target) | target).collect()));
+            }
+            else {
+                self.push_undo(FileOp::Move(result.completed));
+            }
+        }
+
+        self.refresh_dir();
+    }
+
+    fn transfer_dialog(&mut self, ctx: &egui::Context) {
+        if let Some(task) = self.transfer_task.as_ref().filter(| task | task.is_large()) {
+            let title = if task.mode() == ClipboardMode::Copy { "Copying" } else { "Moving" };
+
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, | ui | {
+                    ui.label(format!("{} {} items into {}", title, task.count(), ExplorerApp::tab_title(task.dest_dir())));
+                    ui.add(egui::ProgressBar::new(task.progress()).desired_width(300.0).show_percentage());
+
+                    if let Some(file) = task.current_file() {
+                        ui.weak(file.file_name().unwrap_or(file.as_os_str()).to_string_lossy().to_string());
+                    }
+
+                    ui.separator();
+
+                    ui.add_enabled_ui(!task.is_cancelled(), | ui | {
+                        if ui.button("Cancel").clicked() {
+                            task.cancel();
+                        }
+                    });
+                })
+            ;
+
+            if ctx.input(| i | i.key_pressed(egui::Key::Escape)) {
+                task.cancel();
+            }
+        }
+    }
+
+    pub fn update_dir_entries(&mut self) {
+        self.entry_limit = self.large_dir_threshold;
+        self.reload_dir_entries();
+
+        self.current_dir_items.clear();
+        self.incoming_items = None;
+        self.loading = true;
+    }
+
+    // Reads the next chunk of a directory that was too big to load at once, or all of it.
+    fn load_more_entries(&mut self, all: bool) {
+        self.entry_limit = if all { usize::MAX } else { self.entry_limit.saturating_add(self.large_dir_threshold) };
+        self.reload_dir_entries();
+    }
+
+    // Reads the current directory again without replacing the listing with a spinner meanwhile.
+    fn reload_dir_entries(&mut self) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let path = self.current_path.clone();
+        let hide_system_files = self.hide_system_files;
+        let entry_limit = self.entry_limit.max(1);
+
+        std::thread::spawn(move || {
+            let update = match ExplorerApp::read_dir_entries(&path, hide_system_files, entry_limit, &sender) {
+                Ok(skipped) => ListingUpdate::Finished(skipped),
+                Err(e) => ListingUpdate::Failed(e)
+            };
+
+            // If another directory was requested in the meantime the receiver is gone,
+            // and the stale listing is just dropped.
+            let _ = sender.send(update);
+        });
+
+        self.dir_receiver = Some(receiver);
+        self.incoming_items = Some(Vec::new());
+
+        // Cheap enough to do right here, and only needed when the directory changes.
+        self.disk_space = ExplorerApp::disk_space(&self.current_path);
+        self.resolved_path = std::fs::canonicalize(&self.current_path).ok().filter(| path | *path != self.current_path);
+    }
+
+    fn load_more_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(| ui | {
+            ui.label(format!("Showing the first {} of {} entries", self.current_dir_items.len(), self.total_entries));
+
+            if self.dir_receiver.is_some() {
+                ui.spinner();
+            }
+            else {
+                if ui.button("Load more").clicked() {
+                    self.load_more_entries(false);
+                }
+
+                if ui.button("Load all").on_hover_text("Could take a while").clicked() {
+                    self.load_more_entries(true);
+                }
+            }
+        });
+
+        ui.separator();
+    }
+
+    fn disk_space(path: &Path) -> Option<(u64, u64)> {
+        let available = fs2::available_space(path).ok()?;
+        let total = fs2::total_space(path).ok()?;
+
+        Some((available, total))
+    }
+
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        if self.watcher.as_ref().map(| watcher | watcher.path() != self.current_path).unwrap_or(true) {
+            self.watcher = Some(DirWatcher::new(ctx, &self.current_path));
+        }
+
+        // Entry indices have to stay put while an entry is being renamed.
+        if self.renaming_entry.is_some() || self.dir_receiver.is_some() {
+            return;
+        }
+
+        if let Some(watcher) = self.watcher.as_mut() {
+            if watcher.poll() {
+                self.reload_dir_entries();
+            }
+            else if watcher.is_pending() {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            }
+        }
+    }
+
+    fn poll_dir_entries(&mut self, ctx: &egui::Context) {
+        loop {
+            let update = match self.dir_receiver.as_ref().map(| receiver | receiver.try_recv()) {
+                Some(Ok(update)) => update,
+                Some(Err(TryRecvError::Empty)) => {
+                    // The listing thread doesn't wake the UI up by itself.
+                    ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                    return;
+                }
+                Some(Err(TryRecvError::Disconnected)) => ListingUpdate::Failed(io::Error::other("the listing stopped unexpectedly")),
+                None => return
+            };
+
+            match update {
+                ListingUpdate::Total(total) => self.total_entries = total,
+                ListingUpdate::Entries(items) => {
+                    if let Some(incoming) = self.incoming_items.as_mut() {
+                        incoming.extend(items);
+                    }
+                    else {
+                        self.loading = false;
+                        self.dir_error = None;
+                        self.current_dir_items.extend(items);
+
+                        // Batches arrive a few times a second at most, sorting each time keeps the rows in place once it's done.
+                        self.sort_entries();
+                    }
+                }
+                ListingUpdate::Finished(skipped) => {
+                    self.finish_listing(skipped);
+                    return;
+                }
+                ListingUpdate::Failed(e) => {
+                    self.dir_receiver = None;
+                    self.incoming_items = None;
+                    self.loading = false;
+                    self.dir_error = Some(ExplorerApp::dir_error_message(&self.current_path, &e));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn finish_listing(&mut self, skipped: usize) {
+        self.dir_receiver = None;
+        self.loading = false;
+        self.dir_error = None;
+
+        if skipped > 0 {
+            self.set_error(format!("Skipped {} entries that couldn't be read", skipped));
+        }
+
+        if let Some(mut items) = self.incoming_items.take() {
+            // Folders that were there already keep their size, only new ones get walked.
+            let sizes: HashMap<&Path, u64> = self.current_dir_items.iter()
+                .filter_map(| entry | entry.computed_size.map(| size | (entry.path.as_path(), size)))
+                .collect()
+            ;
+
+            for item in items.iter_mut() {
+                item.computed_size = sizes.get(item.path.as_path()).copied();
+            }
+
+            // Reloads of the same directory keep the selection on the same entries.
+            let selected_path = self.selected_entry
+                .and_then(| idx | self.current_dir_items.get(idx))
+                .map(| entry | entry.path.clone())
+            ;
+
+            let selected_paths: Vec<PathBuf> = self.selected_entries.iter()
+                .filter_map(| idx | self.current_dir_items.get(*idx))
+                .map(| entry | entry.path.clone())
+                .collect()
+            ;
+
+            let renaming_path = self.renaming_entry
+                .and_then(| idx | self.current_dir_items.get(idx))
+                .map(| entry | entry.path.clone())
+            ;
+
+            self.current_dir_items = items;
+
+            // The rename would go to whatever entry ended up at the old index otherwise.
+            if renaming_path.is_some() {
+                self.renaming_entry = renaming_path.and_then(| path | self.current_dir_items.iter().position(| entry | entry.path == path));
+
+                if self.renaming_entry.is_none() {
+                    self.renaming_string = String::new();
+                }
+            }
+
+            self.selected_entry = selected_path.and_then(| path | self.current_dir_items.iter().position(| entry | entry.path == path));
+            self.selected_entries = self.current_dir_items.iter()
+                .enumerate()
+                .filter(| (_, entry) | selected_paths.contains(&entry.path))
+                .map(| (idx, _) | idx)
+                .collect()
+            ;
+        }
+
+        self.sort_entries();
+        self.compute_folder_sizes();
+
+        if let Some(path) = self.pending_select.take() {
+            if let Some(idx) = self.current_dir_items.iter().position(| entry | entry.path == path) {
+                self.select_only(idx);
+                self.scroll_to_selected = true;
+
+                if self.pending_rename {
+                    self.renaming_entry = Some(idx);
+                    self.renaming_string = self.current_dir_items[idx].name.clone();
+                }
+            }
+        }
+
+        self.pending_rename = false;
+    }
+
+    fn compute_folder_sizes(&mut self) {
+        // Walking /proc or /sys would never end, and the sizes there are meaningless anyway.
+        if fs_utils::is_pseudo_fs(&self.current_path) {
+            self.folder_size_receiver = None;
+            return;
+        }
+
+        let folders: Vec<PathBuf> = self.current_dir_items.iter()
+            .filter(| entry | entry._type == EntryType::Folder && entry.computed_size.is_none())
+            .map(| entry | entry.path.clone())
+            .collect()
+        ;
+
+        if folders.is_empty() {
+            self.folder_size_receiver = None;
+            return;
+        }
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            for folder in folders {
+                let (size, _) = fs_utils::dir_size(&folder);
+
+                // Stop walking once the listing this was started for is gone.
+                if sender.send((folder, size)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.folder_size_receiver = Some(receiver);
+    }
+
+    fn poll_folder_sizes(&mut self, ctx: &egui::Context) {
+        let mut finished = false;
+        let mut received = false;
+
+        if let Some(receiver) = self.folder_size_receiver.as_ref() {
+            loop {
+                match receiver.try_recv() {
+                    Ok((path, size)) => {
+                        if let Some(entry) = self.current_dir_items.iter_mut().find(| entry | entry.path == path) {
+                            entry.computed_size = Some(size);
+                            received = true;
+                        }
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+
+            // Nothing else wakes the UI up while the sizes trickle in.
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+
+        if finished {
+            self.folder_size_receiver = None;
+        }
+
+        // Folders move to where their size puts them as it comes in.
+        if received && self.sort_column == SortColumn::Size && self.renaming_entry.is_none() {
+            self.sort_entries();
+        }
+    }
+
+    fn poll_archive_task(&mut self, ctx: &egui::Context) {
+        let result = match self.archive_task.as_mut() {
+            Some(task) => task.poll(),
+            None => return
+        };
+
+        match result {
+            Some(Ok(path)) => {
+                self.archive_task = None;
+                self.push_undo(FileOp::Create(vec![path]));
+                self.refresh_dir();
+            }
+            Some(Err(e)) => {
+                self.archive_task = None;
+                self.set_error(format!("Archive operation failed: {}", e));
+            }
+            None => ctx.request_repaint_after(std::time::Duration::from_millis(100))
+        }
+    }
+
+    fn dir_error_message(path: &Path, error: &io::Error) -> String {
+        if !path.exists() {
+            "it doesn't exist".to_string()
+        }
+        else if !path.is_dir() {
+            "it isn't a folder".to_string()
+        }
+        else if error.kind() == io::ErrorKind::PermissionDenied {
+            "permission denied".to_string()
+        }
+        else {
+            error.to_string()
+        }
+    }
+
+    // Only the first `limit` entries get their details read, the rest are just counted.
+    // Sends the entries over in batches and returns how many couldn't be read.
+    fn read_dir_entries(path: &Path, hide_system_files: bool, limit: usize, sender: &Sender<ListingUpdate>) -> io::Result<usize> {
+        // Often enough to look responsive, not so often that sorting each batch adds up.
+        const BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
-        egui::TopBottomPanel::top("current_path").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.add_enabled_ui(!self.previous_path.is_empty(), |ui| {
-                    if ui.small_button("⏴").clicked() {
-                        self.previous_dir();
-                    }
-                });
+        let mut skipped = 0;
 
-                ui.add_enabled_ui(!self.forward_path.is_empty(), |ui| {
-                    if ui.small_button("⏵").clicked() {
-                        self.forward_dir();
-                    }
-                });
+        // Listing the names alone is cheap, even for huge directories.
+        let entries: Vec<std::fs::DirEntry> = std::fs::read_dir(path)?
+            .filter_map(| entry | {
+                if entry.is_err() {
+                    skipped += 1;
+                }
 
-                ui.add_enabled_ui(self.current_path.parent().is_some(), |ui| {
-                    if ui.small_button("⏶").clicked() {
-                        self.previous_level();
-                    }
-                });
+                entry.ok()
+            })
+            .collect()
+        ;
 
-                ui.separator();
+        let total = entries.len();
+        let _ = sender.send(ListingUpdate::Total(total));
 
-                if ui.small_button("↻").clicked() {
-                    self.refresh_dir();
+        // Sniffing the contents of files in there can block forever, /proc/kmsg being one of them.
+        let pseudo_fs = fs_utils::is_pseudo_fs(path);
+        // Opening every file of a huge directory takes ages, the extension is enough when it's a known one.
+        let large_dir = total > limit;
+
+        let mut items = Vec::new();
+        let mut last_batch = Instant::now();
+
+        for entry in entries.into_iter().take(limit) {
+            if last_batch.elapsed() >= BATCH_INTERVAL && !items.is_empty() {
+                // Nobody's waiting for the rest once the directory isn't open anymore.
+                if sender.send(ListingUpdate::Entries(std::mem::take(&mut items))).is_err() {
+                    return Ok(skipped);
+                }
+
+                last_batch = Instant::now();
+            }
+
+            // Doesn't follow links, so a link to something unreadable still shows up.
+            if let Ok(metadata) = entry.metadata() {
+                if hide_system_files && fs_utils::is_system_file(&metadata) {
+                    continue;
                 }
 
-                if self.editing_current_path {
-                    if PathBuf::from(&self.current_path_str).exists() {
-                        ui.visuals_mut().override_text_color = Some(egui::Color32::from_rgb(0, 255, 0));
+                // file_type() doesn't follow links, so check for those before classifying the entry.
+                let is_symlink = entry.file_type().map(| t | t.is_symlink()).unwrap_or(false);
+
+                let entry_type = {
+                    if is_symlink {
+                        EntryType::Symlink
+                    }
+                    else if metadata.is_dir() {
+                        EntryType::Folder
                     }
                     else {
-                        ui.visuals_mut().override_text_color = Some(egui::Color32::from_rgb(255, 0, 0));
+                        EntryType::File
                     }
-                }
+                };
 
-                let path_text = ui.text_edit_singleline(&mut self.current_path_str);
-                
-                self.editing_current_path = path_text.has_focus();
+                let link_target_type = {
+                    if is_symlink {
+                        std::fs::metadata(entry.path()).ok().map(| target | if target.is_dir() { EntryType::Folder } else { EntryType::File })
+                    }
+                    else {
+                        None
+                    }
+                };
 
-                if path_text.lost_focus() && ui.input(| i | i.key_down(egui::Key::Enter)) {
-                    self.change_dir(PathBuf::from(&self.current_path_str));
-                }
+                let link_target = if is_symlink { std::fs::read_link(entry.path()).ok() } else { None };
 
-                ui.visuals_mut().override_text_color = None;
-            });
-        });
+                let entry_file_name = entry.file_name();
+                let entry_name = entry_file_name.to_string_lossy().to_string();
+                let entry_path = entry.path();
+                let entry_extension = entry.path().extension().unwrap_or_default().to_string_lossy().to_string();
+                let (entry_type_name, entry_category, entry_media_type) = match entry_type {
+                    EntryType::File => {
+                        let skip_sniffing = pseudo_fs || (large_dir && file_types::is_known_extension(&entry_extension));
+                        let format = if skip_sniffing { None } else { file_format::FileFormat::from_file(&entry_path).ok() };
+                        let media_type = format.as_ref().map(| f | f.media_type().to_string());
+                        let (type_name, category) = file_types::type_info(&entry_extension, media_type.as_deref());
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            egui::ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| {
-                self.fill_files_table(ui);
-            });
-        });
-    }
+                        (type_name, category, media_type)
+                    }
+                    EntryType::Folder => ("Folder".to_string(), None, None),
+                    EntryType::Symlink => {
+                        let type_label = match link_target_type {
+                            Some(EntryType::Folder) => "Symlink → Folder",
+                            Some(_) => "Symlink → File",
+                            None => "Broken symlink"
+                        };
 
-    fn fill_files_table(&mut self, ui: &mut egui::Ui) {
-        let text_size = egui::TextStyle::Body.resolve(ui.style()).size + 10.0;
-        let mut new_path = None;
-
-        TableBuilder::new(ui)
-            .column(egui_extras::Column::initial(300.0))
-            .column(egui_extras::Column::initial(100.0))
-            .column(egui_extras::Column::initial(80.0))
-            .column(egui_extras::Column::initial(100.0))
-            .column(egui_extras::Column::initial(100.0))
-            .column(egui_extras::Column::initial(100.0))
-            .column(egui_extras::Column::remainder())
-            .resizable(true)
-            .striped(true)
-            .header(20.0, | mut header | {
-                header.col(| ui | { ui.strong("Name"); });
-                header.col(| ui | { ui.strong("Type"); });
-                header.col(| ui | { ui.strong("Size"); });
-                header.col(| ui | { ui.strong("Creation date"); });
-                header.col(| ui | { ui.strong("Last accessed"); });
-                header.col(| ui | { ui.strong("Last modified"); });
-                header.col(| ui | { ui.strong("Permissions"); });
-            })
-            .body(| body | {
-                body.rows(text_size, self.current_dir_items.len(), | mut row | {
-                    let row_idx = row.index();
+                        (type_label.to_string(), None, None)
+                    }
+                };
 
-                    if let Some(entry) = self.current_dir_items.get(row_idx) {
-                        let (entry_name, entry_type) = match entry._type {
-                            EntryType::File => {
-                                let file_type = {
-                                    if let Ok(t) = file_format::FileFormat::from_file(&entry.path) {
-                                        if let Some(short_name) = t.short_name() {
-                                            format!("{} File", short_name)
-                                        }
-                                        else {
-                                            "File".to_string()
-                                        }
-                                    }
-                                    else {
-                                        "File".to_string()
-                                    }
-                                };
-            
-                                (format!("🗋 {}", entry.name), file_type)
-                            }
-                            EntryType::Folder => (format!("🗁 {}", entry.name), "Folder".to_string()),
-                            EntryType::Symlink => (format!("🔗 {}", entry.name), "Symlink".to_string())
-                        };
+                let entry_length = metadata.len() as usize;
+                let entry_permissions = fs_utils::permissions_string(&metadata);
+                let (entry_owner, entry_group) = fs_utils::owner_and_group(&metadata).unzip();
+                let entry_executable = fs_utils::is_executable(&entry_path, &metadata);
 
-                        row.col(| ui | {
-                            let renaming = {
-                                if let Some(target) = self.renaming_entry.as_ref() {
-                                    row_idx == *target
-                                }
-                                else {
-                                    false
-                                }
-                            };
-
-                            if renaming {
-                                // Red highlight for the text if there is a file with the same name.
-                                if entry.name != self.renaming_string {
-                                    if let Some(path) = entry.path.parent() {
-                                        if path.join(PathBuf::from(&self.renaming_string)).exists() {
-                                            ui.visuals_mut().override_text_color = Some(egui::Color32::from_rgb(255, 0, 0));
-                                        }
-                                    }
-                                }
+                let dir_entry = EntryInfo {
+                    _type: entry_type,
+                    link_target_type,
+                    link_target,
 
-                                let entry_label = {
-                                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                        ui.text_edit_singleline(&mut self.renaming_string)
-                                    }).response
-                                };
-
-                                if entry_label.lost_focus() {
-                                    // User committed the changes.
-                                    if ui.input(| i | i.key_pressed(egui::Key::Enter)) {
-                                        // Check if an entry with the same name already exists.
-                                        if let Some(parent) = entry.path.parent() {
-                                            let new_entry = parent.join(PathBuf::from(&self.renaming_string));
-                                        
-                                            // There's already an entry on this directory with that name, don't rename.
-                                            if !new_entry.exists() {
-                                                if let Err(e) = std::fs::rename(&entry.path, new_entry) {
-                                                    println!("{}", e);
-                                                }   
-                                            }
-                                        }
-                                    }
+                    name: entry_name,
+                    file_name: entry_file_name,
+                    path: entry_path,
+                    extension: entry_extension,
+                    type_name: entry_type_name,
+                    category: entry_category,
+                    media_type: entry_media_type,
+                    length: entry_length,
+                    permissions: entry_permissions,
+                    owner: entry_owner,
+                    group: entry_group,
+                    executable: entry_executable,
 
-                                    // Forcing a refresh for the current dir.
-                                    new_path = Some(self.current_path.clone());
-                    
-                                    self.renaming_entry = None;
-                                    self.renaming_string = String::new();
-                                }
-                                else {
-                                    entry_label.request_focus();
-                                }
+                    computed_size: None,
 
-                                ui.visuals_mut().override_text_color = None;
-                            }
-                            else {
-                                let is_selected = {
-                                    if let Some(selection) = self.selected_entry.as_ref() {
-                                        *selection == row_idx
-                                    }
-                                    else {
-                                        false
-                                    }
-                                };
-                                
-                                let entry_label = {
-                                    ui.push_id(&entry.name, | ui | {
-                                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                            ui.selectable_label(is_selected, entry_name)
-                                        }).inner
-                                    }).inner
-                                };
-                
-                                if entry_label.double_clicked() {
-                                    if entry._type == EntryType::File {
-                                        open::that_in_background(&entry.path);
-                                    }
-                                    else {
-                                        new_path = Some(entry.path.clone());
-                                    }
-                    
-                                    self.selected_entry = Some(row_idx);
-                                }
-                                else if entry_label.clicked() {
-                                    self.selected_entry = Some(row_idx);
-                                }
-                
-                                entry_label.context_menu(| ui | {
-                                    if ui.selectable_label(false, "Open").clicked() {
-                                        if entry.path.exists() {
-                                            if entry._type == EntryType::File {
-                                                open::that_in_background(&entry.path);
-                                            }
-                                            else {
-                                                new_path = Some(entry.path.clone());
-                                            }
-                                        }
-                
-                                        ui.close_menu();
-                                    }
+                    modified_at: metadata.modified().ok(),
+                    accessed_at: metadata.accessed().ok(),
+                    created_at: metadata.created().ok()
+                };
 
-                                    if entry._type == EntryType::Folder {
-                                        if ui.selectable_label(false, "Open in new window").clicked() {
-                                            ui.close_menu();
-                                            
-                                            let vp_id = egui::ViewportId::from_hash_of(&entry.path);
-                                            let vp_builder = egui::ViewportBuilder::default()
-                                                .with_title("explorer-rs")
-                                            ;
+                items.push(dir_entry);
+            }
+            else {
+                skipped += 1;
+            }
+        }
 
-                                            let entry_path = entry.path.clone();
+        let _ = sender.send(ListingUpdate::Entries(items));
 
-                                            ui.ctx().show_viewport_deferred(vp_id, vp_builder, move | ctx, _ | {
-                                                let entry_path = &entry_path;
-                                                let mut new_state = ExplorerApp::default();
-                                                new_state.change_dir(entry_path.to_path_buf());
+        Ok(skipped)
+    }
 
-                                                new_state.main_app(ctx);
-                                            });
-                                        }
-                                    }
-                
-                                    ui.separator();
-                
-                                    // TODO.
-                                    ui.add_enabled_ui(false, |ui| {
-                                        if ui.selectable_label(false, "Cut").clicked() {
-                                            ui.close_menu();
-                                        }
-                                    });
-                
-                                    // TODO.
-                                    ui.add_enabled_ui(false, |ui| {
-                                        if ui.selectable_label(false, "Copy").clicked() {
-                                            ui.close_menu();
-                                        }
-                                    });
-                
-                                    ui.separator();
-                
-                                    if ui.selectable_label(false, "Rename").clicked() {
-                                        self.renaming_entry = Some(row_idx);
-                                        self.renaming_string = entry.name.clone();
-                
-                                        ui.close_menu();
-                                    }
-                
-                                    // TODO: This could use a confirmation prompt.
-                                    if ui.selectable_label(false, "Remove").clicked() {
-                                        if entry._type == EntryType::Folder {
-                                            if let Err(e) = std::fs::remove_dir_all(&entry.path) {
-                                                println!("{}", e);
-                                            }
-                                        }
-                                        else if let Err(e) = std::fs::remove_file(&entry.path) {
-                                            println!("{}", e);
-                                        }
-                
-                                        new_path = Some(self.current_path.clone());
-                                        ui.close_menu();
-                                    }
-                                });
-                            }
-                        });
+    fn sort_entries(&mut self) {
+        // Keep the selection pointing at the same entries after they move around.
+        let selected_path = self.selected_entry
+            .and_then(| idx | self.current_dir_items.get(idx))
+            .map(| entry | entry.path.clone())
+        ;
 
-                        row.col(| ui | {
-                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                ui.label(entry_type);
-                            });
-                        });
+        let selected_paths: Vec<PathBuf> = self.selected_entries.iter()
+            .filter_map(| idx | self.current_dir_items.get(*idx))
+            .map(| entry | entry.path.clone())
+            .collect()
+        ;
 
-                        row.col(| ui | {
-                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                ui.label(ExplorerApp::size_to_string(entry.length)); 
-                            });
-                        });
+        let renaming_path = self.renaming_entry
+            .and_then(| idx | self.current_dir_items.get(idx))
+            .map(| entry | entry.path.clone())
+        ;
 
-                        row.col(| ui | {
-                            if let Some(creation_time) = entry.last_modification.as_ref() {
-                                ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                    ui.label(&ExplorerApp::duration_to_string(creation_time));
-                                });
-                            }
-                        });
+        let column = self.sort_column;
+        let ascending = self.sort_ascending;
+        let folders_first = self.folders_first;
+        let size_sort_folders_first = self.size_sort_folders_first;
+        let natural_sort = self.natural_sort;
+        let case_sensitive = self.case_sensitive;
 
-                        row.col(| ui | {
-                            if let Some(last_accessed) = entry.last_accessed.as_ref() {
-                                ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                    ui.label(&ExplorerApp::duration_to_string(last_accessed));
-                                });
-                            }
-                        });
+        self.current_dir_items.sort_by(| a, b | {
+            let ordering = ExplorerApp::compare_entries(a, b, column, natural_sort, case_sensitive);
+            let ordering = if ascending { ordering } else { ordering.reverse() };
 
-                        row.col(| ui | {
-                            if let Some(last_modified) = entry.last_modified.as_ref() {
-                                ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                    ui.label(&ExplorerApp::duration_to_string(last_modified));
-                                });
-                            }
-                        });
+            // Lots of entries share a size or a time, keep those in name order instead of
+            // whatever order the directory happened to be read in.
+            let ordering = ordering
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+                .then_with(|| a.name.cmp(&b.name))
+            ;
 
-                        row.col(| ui | {
-                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), | ui | {
-                                ui.label(&entry.permissions); 
-                            });
-                        });
-                    }
-                });
-            })
+            // Folders with no size to go by stay together at one end, instead of mixing with tiny files.
+            let unsized_first = {
+                if column == SortColumn::Size {
+                    let a_unsized = ExplorerApp::sort_size(a).is_none();
+                    let b_unsized = ExplorerApp::sort_size(b).is_none();
+
+                    if size_sort_folders_first { b_unsized.cmp(&a_unsized) } else { a_unsized.cmp(&b_unsized) }
+                }
+                else {
+                    Ordering::Equal
+                }
+            };
+
+            let ordering = unsized_first.then(ordering);
+
+            if folders_first {
+                let a_is_folder = a.is_folder();
+                let b_is_folder = b.is_folder();
+
+                b_is_folder.cmp(&a_is_folder).then(ordering)
+            }
+            else {
+                ordering
+            }
+        });
+
+        self.selected_entry = selected_path.and_then(| path | {
+            self.current_dir_items.iter().position(| entry | entry.path == path)
+        });
+
+        self.selected_entries = self.current_dir_items.iter()
+            .enumerate()
+            .filter(| (_, entry) | selected_paths.contains(&entry.path))
+            .map(| (idx, _) | idx)
+            .collect()
         ;
 
-        if let Some(new_path) = new_path {
-            self.change_dir(new_path);
+        if let Some(path) = renaming_path {
+            self.renaming_entry = self.current_dir_items.iter().position(| entry | entry.path == path);
         }
     }
 
-    pub fn update_dir_entries(&mut self) {
-        if let Ok(entries) = std::fs::read_dir(&self.current_path) {
-            let mut dirs = Vec::new();
-            let mut files = Vec::new();
+    fn compare_entries(a: &EntryInfo, b: &EntryInfo, column: SortColumn, natural_sort: bool, case_sensitive: bool) -> Ordering {
+        match column {
+            SortColumn::Name if natural_sort => ExplorerApp::natural_cmp(&a.name, &b.name, case_sensitive),
+            SortColumn::Name if case_sensitive => a.name.cmp(&b.name),
+            SortColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortColumn::Type => a.extension.to_lowercase().cmp(&b.extension.to_lowercase()),
+            SortColumn::Size => ExplorerApp::sort_size(a).cmp(&ExplorerApp::sort_size(b)),
+            SortColumn::Created => a.created_at.cmp(&b.created_at),
+            SortColumn::Accessed => a.accessed_at.cmp(&b.accessed_at),
+            SortColumn::Modified => a.modified_at.cmp(&b.modified_at),
+            SortColumn::Permissions => a.permissions.cmp(&b.permissions),
+            SortColumn::Owner => a.owner.cmp(&b.owner),
+            SortColumn::Group => a.group.cmp(&b.group)
+        }
+    }
 
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
-                    let entry_type = {
-                        if metadata.is_file() {
-                            EntryType::File
-                        }
-                        else if metadata.is_dir() {
-                            EntryType::Folder
-                        }
-                        else {
-                            EntryType::Symlink
-                        }
-                    };
+    // The size of a folder is whatever was added up for it, if that's done already.
+    fn sort_size(entry: &EntryInfo) -> Option<u64> {
+        // Links to folders aren't walked, they go by their own length like in the Size column.
+        if entry._type == EntryType::Folder {
+            entry.computed_size
+        }
+        else {
+            Some(entry.length as u64)
+        }
+    }
 
-                    let entry_name = entry.file_name().into_string().unwrap_or_default();
-                    let entry_path = entry.path();
-                    let entry_extension = entry.path().extension().unwrap_or_default().to_str().unwrap_or_default().to_string();
-                    let entry_length = metadata.len() as usize;
-                    let entry_permissions = if metadata.permissions().readonly() { "r".to_string() } else { "rw".to_string() };
+    // Comparison that splits names into text and number chunks, comparing numbers
+    // by their value instead of digit by digit.
+    fn natural_cmp(a: &str, b: &str, case_sensitive: bool) -> Ordering {
+        let (a, b) = if case_sensitive { (a.to_string(), b.to_string()) } else { (a.to_lowercase(), b.to_lowercase()) };
 
-                    let last_modified = {
-                        if let Ok(modified) = metadata.modified() {
-                            if let Ok(modified) = modified.elapsed() {
-                                Duration::try_from(modified).ok()
-                            }
-                            else {
-                                None
-                            }
-                        }
-                        else {
-                            None
-                        }
-                    };
+        let mut a_chars = a.chars().peekable();
+        let mut b_chars = b.chars().peekable();
 
-                    let last_accessed = {
-                        if let Ok(accessed) = metadata.accessed() {
-                            if let Ok(accessed) = accessed.elapsed() {
-                                Duration::try_from(accessed).ok()
-                            }
-                            else {
-                                None
-                            }
-                        }
-                        else {
-                            None
-                        }
-                    };
+        loop {
+            let (a_char, b_char) = match (a_chars.peek(), b_chars.peek()) {
+                (Some(a_char), Some(b_char)) => (*a_char, *b_char),
+                (a_char, b_char) => return a_char.is_some().cmp(&b_char.is_some())
+            };
 
-                    let creation_time = {
-                        if let Ok(created) = metadata.created() {
-                            if let Ok(created) = created.elapsed() {
-                                Duration::try_from(created).ok()
-                            }
-                            else {
-                                None
-                            }
-                        }
-                        else {
-                            None
-                        }
-                    };
+            if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+                let mut a_number = String::new();
+                let mut b_number = String::new();
 
-                    let dir_entry = EntryInfo {
-                        _type: entry_type,
+                while let Some(c) = a_chars.next_if(| c | c.is_ascii_digit()) {
+                    a_number.push(c);
+                }
 
-                        name: entry_name,
-                        path: entry_path,
-                        extension: entry_extension,
-                        length: entry_length,
-                        permissions: entry_permissions,
+                while let Some(c) = b_chars.next_if(| c | c.is_ascii_digit()) {
+                    b_number.push(c);
+                }
 
-                        last_modified,
-                        last_accessed,
-                        last_modification: creation_time
-                    };
+                let a_value = a_number.trim_start_matches('0');
+                let b_value = b_number.trim_start_matches('0');
 
-                    if metadata.is_dir() {
-                        dirs.push(dir_entry);
-                    }
-                    else {
-                        files.push(dir_entry);
-                    }
+                // With leading zeros gone, a longer run is a bigger number. Equal values
+                // with different padding ("01" and "1") keep the shorter one first.
+                let ordering = a_value.len().cmp(&b_value.len())
+                    .then_with(|| a_value.cmp(b_value))
+                    .then_with(|| a_number.len().cmp(&b_number.len()))
+                ;
+
+                if ordering != Ordering::Equal {
+                    return ordering;
                 }
             }
+            else {
+                if a_char != b_char {
+                    return a_char.cmp(&b_char);
+                }
 
-            dirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-            files.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-
-            let mut entries = Vec::new();
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
 
-            entries.append(&mut dirs);
-            entries.append(&mut files);
+    pub fn size_to_string(bytes: usize, binary_units: bool) -> String {
+        bytesize::to_string(bytes as u64, binary_units)
+    }
 
-            self.current_dir_items = entries;
+    pub fn time_to_string(display: TimeDisplay, time: Option<&SystemTime>) -> Option<String> {
+        match display {
+            TimeDisplay::Relative => {
+                let elapsed = time?.elapsed().ok()?;
+                Duration::try_from(elapsed).ok().map(| elapsed | ExplorerApp::duration_to_string(&elapsed))
+            }
+            TimeDisplay::Absolute => time.map(ExplorerApp::system_time_to_string)
         }
     }
 
-    pub fn size_to_string(bytes: usize) -> String {
-        bytesize::to_string(bytes as u64, false)
+    pub fn system_time_to_string(system_time: &SystemTime) -> String {
+        let format = time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]");
+
+        time::OffsetDateTime::from(*system_time)
+            .to_offset(local_offset())
+            .format(format)
+            .unwrap_or_default()
     }
 
     pub fn duration_to_string(duration: &Duration) -> String {
@@ -579,7 +5156,7 @@ impl ExplorerApp {
             format!("{} days ago", duration.whole_days())
         }
         else if duration.whole_hours() >= 1 {
-            format!("{} hours ago", duration.whole_days())
+            format!("{} hours ago", duration.whole_hours())
         }
         else if duration.whole_minutes() >= 1 {
             format!("{} minutes ago", duration.whole_minutes())
@@ -590,11 +5167,241 @@ impl ExplorerApp {
     }
 }
 
+// The local offset can only be queried reliably before other threads are around, so it's
+// resolved once on startup and reused afterwards.
+fn local_offset() -> time::UtcOffset {
+    static LOCAL_OFFSET: OnceLock<time::UtcOffset> = OnceLock::new();
+
+    *LOCAL_OFFSET.get_or_init(|| time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC))
+}
+
 fn main() {
-    let mut app = ExplorerApp::default();
+    local_offset();
+
     let native_options = eframe::NativeOptions::default();
 
-    app.update_dir_entries();
+    eframe::run_native("explorer-rs", native_options, Box::new(| cc | Box::new(ExplorerApp::restore(cc.storage))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_to_string_boundaries() {
+        let cases = [
+            (Duration::seconds(59), "59 seconds ago"),
+            (Duration::minutes(1), "1 minutes ago"),
+            (Duration::minutes(59), "59 minutes ago"),
+            (Duration::hours(1), "1 hours ago"),
+            (Duration::hours(23), "23 hours ago"),
+            (Duration::days(1), "1 days ago"),
+            (Duration::days(6), "6 days ago"),
+            (Duration::weeks(2), "2 weeks ago")
+        ];
+
+        for (duration, expected) in cases {
+            assert_eq!(ExplorerApp::duration_to_string(&duration), expected);
+        }
+    }
+
+    fn naturally_sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(| name | name.to_string()).collect();
+        names.sort_by(| a, b | ExplorerApp::natural_cmp(a, b, false));
+
+        names
+    }
+
+    #[test]
+    fn natural_sort_compares_numbers_by_value() {
+        assert_eq!(naturally_sorted(&["img10", "img2", "img1"]), ["img1", "img2", "img10"]);
+        assert_eq!(naturally_sorted(&["file10.txt", "file2.txt", "file1.txt"]), ["file1.txt", "file2.txt", "file10.txt"]);
+    }
+
+    #[test]
+    fn natural_sort_ignores_case() {
+        assert_eq!(naturally_sorted(&["IMG10", "img9", "Img1"]), ["Img1", "img9", "IMG10"]);
+        assert_eq!(naturally_sorted(&["b", "A", "c", "B2"]), ["A", "b", "B2", "c"]);
+    }
+
+    #[test]
+    fn natural_sort_multiple_numbers() {
+        let names = ["v1.10.2", "v1.2.10", "v1.2.9", "v10.0.0", "v1.10.10"];
+        assert_eq!(naturally_sorted(&names), ["v1.2.9", "v1.2.10", "v1.10.2", "v1.10.10", "v10.0.0"]);
+
+        let names = ["track 2 - part 10", "track 10 - part 1", "track 2 - part 9"];
+        assert_eq!(naturally_sorted(&names), ["track 2 - part 9", "track 2 - part 10", "track 10 - part 1"]);
+    }
+
+    #[test]
+    fn natural_sort_leading_zeros_and_prefixes() {
+        assert_eq!(naturally_sorted(&["a010", "a9", "a01", "a1"]), ["a1", "a01", "a9", "a010"]);
+        assert_eq!(naturally_sorted(&["img", "img1", "im"]), ["im", "img", "img1"]);
+        assert_eq!(naturally_sorted(&["a", "1", "10", "2"]), ["1", "2", "10", "a"]);
+    }
+
+    fn file_entry(name: &str, length: usize) -> EntryInfo {
+        EntryInfo {
+            _type: EntryType::File,
+            link_target_type: None,
+            link_target: None,
+
+            name: name.to_string(),
+            file_name: OsString::from(name),
+            path: PathBuf::from(name),
+            extension: String::new(),
+            type_name: "File".to_string(),
+            category: None,
+            media_type: None,
+            length,
+            permissions: "rw".to_string(),
+            owner: None,
+            group: None,
+            executable: false,
+
+            computed_size: None,
+
+            modified_at: None,
+            accessed_at: None,
+            created_at: None
+        }
+    }
+
+    fn folder_entry(name: &str, computed_size: Option<u64>) -> EntryInfo {
+        EntryInfo {
+            _type: EntryType::Folder,
+            type_name: "Folder".to_string(),
+            length: 4096,
+            computed_size,
+            ..file_entry(name, 0)
+        }
+    }
+
+    fn sorted_by_size(names: &[(&str, usize)], ascending: bool) -> Vec<String> {
+        let mut app = ExplorerApp::default();
+
+        app.sort_column = SortColumn::Size;
+        app.sort_ascending = ascending;
+        app.current_dir_items = names.iter().map(| (name, length) | file_entry(name, *length)).collect();
+        app.sort_entries();
 
-    eframe::run_native("explorer-rs", native_options, Box::new(|_| Box::new(app)));
+        app.current_dir_items.iter().map(| entry | entry.name.clone()).collect()
+    }
+
+    #[test]
+    fn equal_sizes_sort_by_name() {
+        let names = [("c.txt", 0), ("A.txt", 0), ("big.bin", 10), ("b.txt", 0)];
+
+        assert_eq!(sorted_by_size(&names, true), ["A.txt", "b.txt", "c.txt", "big.bin"]);
+        assert_eq!(sorted_by_size(&names, false), ["big.bin", "A.txt", "b.txt", "c.txt"]);
+
+        // The order the entries were read in doesn't matter.
+        let reversed: Vec<(&str, usize)> = names.iter().rev().copied().collect();
+        assert_eq!(sorted_by_size(&reversed, true), sorted_by_size(&names, true));
+    }
+
+    fn enter_event(pressed: bool, repeat: bool) -> egui::Event {
+        egui::Event::Key {
+            key: egui::Key::Enter,
+            physical_key: None,
+            pressed,
+            repeat,
+            modifiers: egui::Modifiers::NONE
+        }
+    }
+
+    #[test]
+    fn only_fresh_enter_presses_commit() {
+        let mut input = egui::InputState::default();
+        assert!(!ExplorerApp::enter_pressed(&input), "losing focus without Enter doesn't commit");
+
+        input.events = vec![enter_event(true, false)];
+        assert!(ExplorerApp::enter_pressed(&input));
+
+        input.events = vec![enter_event(true, true)];
+        assert!(!ExplorerApp::enter_pressed(&input), "holding Enter down doesn't commit again");
+
+        input.events = vec![enter_event(false, false)];
+        assert!(!ExplorerApp::enter_pressed(&input), "releasing Enter doesn't commit");
+    }
+
+    #[test]
+    fn global_sort_order_applies_everywhere() {
+        let mut app = ExplorerApp::default();
+        let first = PathBuf::from("first");
+        let second = PathBuf::from("second");
+
+        app.current_path = first.clone();
+        app.sort_column = SortColumn::Size;
+        app.save_view_state();
+
+        app.current_path = second.clone();
+        app.sort_column = SortColumn::Modified;
+        app.save_view_state();
+
+        app.current_path = first.clone();
+        app.restore_view_state();
+        assert!(app.sort_column == SortColumn::Size);
+
+        // Sorting while global doesn't touch what each folder remembers.
+        app.sort_per_directory = false;
+        app.sort_column = SortColumn::Type;
+        app.save_view_state();
+
+        app.current_path = second.clone();
+        app.restore_view_state();
+        assert!(app.sort_column == SortColumn::Type);
+
+        app.sort_per_directory = true;
+        app.restore_view_state();
+        assert!(app.sort_column == SortColumn::Modified);
+
+        app.current_path = first;
+        app.restore_view_state();
+        assert!(app.sort_column == SortColumn::Size);
+    }
+
+    fn folders_sorted_by_size(ascending: bool, size_sort_folders_first: bool) -> Vec<String> {
+        let mut app = ExplorerApp::default();
+
+        app.sort_column = SortColumn::Size;
+        app.sort_ascending = ascending;
+        app.folders_first = false;
+        app.size_sort_folders_first = size_sort_folders_first;
+        app.current_dir_items = vec![
+            file_entry("small.txt", 10),
+            folder_entry("pending", None),
+            file_entry("big.bin", 10_000),
+            folder_entry("medium", Some(500)),
+            EntryInfo {
+                _type: EntryType::Symlink,
+                link_target_type: Some(EntryType::Folder),
+                ..file_entry("link", 20)
+            },
+            folder_entry("waiting", None)
+        ];
+        app.sort_entries();
+
+        app.current_dir_items.iter().map(| entry | entry.name.clone()).collect()
+    }
+
+    #[test]
+    fn rename_follows_its_entry_when_sorting() {
+        let mut app = ExplorerApp::default();
+
+        app.sort_column = SortColumn::Size;
+        app.current_dir_items = vec![file_entry("big.bin", 100), file_entry("small.txt", 1)];
+        app.renaming_entry = Some(0);
+        app.sort_entries();
+
+        assert_eq!(app.renaming_entry.map(| idx | app.current_dir_items[idx].name.as_str()), Some("big.bin"));
+    }
+
+    #[test]
+    fn folders_without_a_size_stay_at_one_end() {
+        assert_eq!(folders_sorted_by_size(true, true), ["pending", "waiting", "small.txt", "link", "medium", "big.bin"]);
+        assert_eq!(folders_sorted_by_size(false, true), ["pending", "waiting", "big.bin", "medium", "link", "small.txt"]);
+        assert_eq!(folders_sorted_by_size(true, false), ["small.txt", "link", "medium", "big.bin", "pending", "waiting"]);
+        assert_eq!(folders_sorted_by_size(false, false), ["big.bin", "medium", "link", "small.txt", "pending", "waiting"]);
+    }
 }
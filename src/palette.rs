@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::keymap::{Action, KeyCombo};
+
+// A box to run any action by typing part of its name.
+#[derive(Default)]
+pub struct CommandPalette {
+    query: String,
+    selected: usize
+}
+
+impl CommandPalette {
+    // Returns the action that was picked, and whether the palette should be closed.
+    pub fn show(&mut self, ctx: &egui::Context, keymap: &HashMap<Action, KeyCombo>) -> (Option<Action>, bool) {
+        let mut picked = None;
+
+        // Taken before the text box sees them, it would move the cursor around otherwise.
+        let (up, down, enter, escape) = ctx.input_mut(| i | {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape)
+            )
+        });
+
+        let window = egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, | ui | {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text("Type a command").desired_width(350.0));
+                response.request_focus();
+
+                let matches = self.matches();
+
+                if response.changed() {
+                    self.selected = 0;
+                }
+                else if down {
+                    self.selected = (self.selected + 1).min(matches.len().saturating_sub(1));
+                }
+                else if up {
+                    self.selected = self.selected.saturating_sub(1);
+                }
+
+                ui.separator();
+
+                if matches.is_empty() {
+                    ui.weak("No matching commands");
+                }
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, | ui | {
+                    for (idx, action) in matches.iter().enumerate() {
+                        ui.horizontal(| ui | {
+                            let response = ui.selectable_label(idx == self.selected, action.label());
+
+                            if idx == self.selected && (up || down) {
+                                response.scroll_to_me(None);
+                            }
+
+                            if response.clicked() {
+                                picked = Some(*action);
+                            }
+
+                            if let Some(combo) = keymap.get(action) {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), | ui | {
+                                    ui.weak(combo.to_string());
+                                });
+                            }
+                        });
+                    }
+                });
+
+                if enter {
+                    picked = picked.or(matches.get(self.selected).copied());
+                }
+            })
+        ;
+
+        let clicked_outside = window.map(| window | window.response.clicked_elsewhere()).unwrap_or(false);
+
+        (picked, picked.is_some() || escape || clicked_outside)
+    }
+
+    // Best matches first, ties keep the order of Action::ALL.
+    fn matches(&self) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = Action::ALL.into_iter()
+            .filter(| action | *action != Action::CommandPalette)
+            .filter_map(| action | fuzzy_score(&self.query, action.label()).map(| score | (score, action)))
+            .collect()
+        ;
+
+        scored.sort_by_key(| (score, _) | std::cmp::Reverse(*score));
+        scored.into_iter().map(| (_, action) | action).collect()
+    }
+}
+
+// How well `text` matches `query`, if all of the query's characters show up in it in order.
+// Characters at the start of a word, or right after the previous match, count for more.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(| c | !c.is_whitespace()) {
+        let idx = (start..text.len()).find(| idx | text[*idx] == c)?;
+
+        score += 1;
+
+        if idx == 0 || !text[idx - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        if previous.map(| previous | previous + 1 == idx).unwrap_or(false) {
+            score += 2;
+        }
+
+        previous = Some(idx);
+        start = idx + 1;
+    }
+
+    Some(score)
+}
@@ -0,0 +1,182 @@
+use std::fs::Metadata;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use eframe::egui;
+
+use crate::ExplorerApp;
+
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
+// Only the start of text files gets read, previews don't need the whole thing.
+const TEXT_PREVIEW_LIMIT: u64 = 64 * 1024;
+// Bigger images are scaled down before being uploaded as a texture.
+const IMAGE_PREVIEW_SIZE: u32 = 1024;
+
+// What the loader thread sends back. Textures have to be created on the UI side.
+enum LoadedPreview {
+    Image(egui::ColorImage),
+    Text(String),
+    Nothing
+}
+
+enum PreviewContent {
+    Loading,
+    Image(egui::TextureHandle),
+    Text(String),
+    Nothing
+}
+
+pub struct Preview {
+    path: PathBuf,
+    metadata: Option<Metadata>,
+
+    content: PreviewContent,
+    receiver: Option<Receiver<LoadedPreview>>
+}
+
+impl Preview {
+    pub fn new(path: &Path) -> Preview {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let target = path.to_path_buf();
+
+        std::thread::spawn(move || {
+            let _ = sender.send(Preview::load(&target));
+        });
+
+        Preview {
+            path: path.to_path_buf(),
+            metadata: std::fs::symlink_metadata(path).ok(),
+
+            content: PreviewContent::Loading,
+            receiver: Some(receiver)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(path: &Path) -> LoadedPreview {
+        if !path.is_file() {
+            return LoadedPreview::Nothing;
+        }
+
+        let extension = path.extension().and_then(| e | e.to_str()).unwrap_or_default().to_lowercase();
+
+        if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            if let Ok(image) = image::open(path) {
+                let image = {
+                    if image.width() > IMAGE_PREVIEW_SIZE || image.height() > IMAGE_PREVIEW_SIZE {
+                        image.thumbnail(IMAGE_PREVIEW_SIZE, IMAGE_PREVIEW_SIZE).to_rgba8()
+                    }
+                    else {
+                        image.to_rgba8()
+                    }
+                };
+
+                let size = [image.width() as usize, image.height() as usize];
+                return LoadedPreview::Image(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()));
+            }
+
+            return LoadedPreview::Nothing;
+        }
+
+        let is_text = file_format::FileFormat::from_file(path)
+            .map(| format | format.media_type().starts_with("text/"))
+            .unwrap_or(false)
+        ;
+
+        if is_text {
+            if let Ok(file) = std::fs::File::open(path) {
+                let mut buffer = Vec::new();
+
+                if file.take(TEXT_PREVIEW_LIMIT).read_to_end(&mut buffer).is_ok() {
+                    return LoadedPreview::Text(String::from_utf8_lossy(&buffer).to_string());
+                }
+            }
+        }
+
+        LoadedPreview::Nothing
+    }
+
+    fn poll(&mut self, ctx: &egui::Context) {
+        if let Some(receiver) = self.receiver.as_ref() {
+            let loaded = match receiver.try_recv() {
+                Ok(loaded) => loaded,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => LoadedPreview::Nothing
+            };
+
+            self.content = match loaded {
+                LoadedPreview::Image(image) => PreviewContent::Image(ctx.load_texture("preview_image", image, egui::TextureOptions::LINEAR)),
+                LoadedPreview::Text(text) => PreviewContent::Text(text),
+                LoadedPreview::Nothing => PreviewContent::Nothing
+            };
+
+            self.receiver = None;
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, binary_units: bool) {
+        self.poll(ui.ctx());
+
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str()).to_string_lossy().to_string();
+
+        ui.strong(&name);
+        ui.separator();
+
+        match &self.content {
+            PreviewContent::Loading => {
+                ui.spinner();
+                ui.ctx().request_repaint();
+            }
+            PreviewContent::Image(texture) => {
+                ui.add(egui::Image::new(texture).shrink_to_fit());
+            }
+            PreviewContent::Text(text) => {
+                let mut text = text.as_str();
+
+                egui::ScrollArea::vertical().max_height(ui.available_height() * 0.75).show(ui, | ui | {
+                    ui.add(egui::TextEdit::multiline(&mut text).code_editor().desired_width(f32::INFINITY));
+                });
+            }
+            PreviewContent::Nothing => {
+                let icon = {
+                    if self.path.is_symlink() {
+                        "🔗"
+                    }
+                    else if self.path.is_dir() {
+                        "🗁"
+                    }
+                    else {
+                        "🗋"
+                    }
+                };
+
+                ui.vertical_centered(| ui | {
+                    ui.label(egui::RichText::new(icon).size(64.0));
+                });
+            }
+        }
+
+        if let Some(metadata) = self.metadata.as_ref() {
+            ui.separator();
+
+            egui::Grid::new("preview_metadata").num_columns(2).show(ui, | ui | {
+                if metadata.is_file() {
+                    ui.strong("Size");
+                    ui.label(ExplorerApp::size_to_string(metadata.len() as usize, binary_units));
+                    ui.end_row();
+                }
+
+                if let Ok(modified) = metadata.modified() {
+                    ui.strong("Modified");
+                    ui.label(ExplorerApp::system_time_to_string(&modified));
+                    ui.end_row();
+                }
+            });
+        }
+    }
+}
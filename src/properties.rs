@@ -0,0 +1,427 @@
+use std::fs::Metadata;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use eframe::egui;
+use sha2::Digest;
+
+use crate::ExplorerApp;
+use crate::file_types;
+use crate::fs_utils;
+
+#[derive(Clone, Copy)]
+enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256
+}
+
+impl HashAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha1 => "SHA-1",
+            HashAlgorithm::Sha256 => "SHA-256"
+        }
+    }
+
+    fn hash_file(&self, path: &Path) -> std::io::Result<String> {
+        match self {
+            HashAlgorithm::Md5 => hash_file::<md5::Md5>(path),
+            HashAlgorithm::Sha1 => hash_file::<sha1::Sha1>(path),
+            HashAlgorithm::Sha256 => hash_file::<sha2::Sha256>(path)
+        }
+    }
+}
+
+enum HashState {
+    NotComputed,
+    Computing(Receiver<Option<String>>),
+    Done(String),
+    Failed
+}
+
+pub struct PropertiesDialog {
+    path: PathBuf,
+    metadata: Option<Metadata>,
+    media_type: Option<String>,
+    link_target: Option<PathBuf>,
+
+    // Total size and item count for folders, filled in by a background thread.
+    folder_size: Option<(u64, usize)>,
+    folder_size_receiver: Option<Receiver<(u64, usize)>>,
+
+    // Only offered for regular files, computed on demand since it means reading the whole file.
+    hashes: Vec<(HashAlgorithm, HashState)>,
+
+    // Permission bits as edited in the dialog, only written once applied. Unix only,
+    // and not for symlinks since changing those changes the target instead.
+    mode: Option<u32>,
+    permissions_error: Option<String>
+}
+
+impl PropertiesDialog {
+    pub fn new(path: &Path) -> PropertiesDialog {
+        let metadata = std::fs::symlink_metadata(path).ok();
+        let is_symlink = metadata.as_ref().map(| m | m.file_type().is_symlink()).unwrap_or(false);
+        let is_dir = path.is_dir();
+
+        let media_type = {
+            if path.is_file() {
+                file_format::FileFormat::from_file(path).ok().map(| f | f.media_type().to_string())
+            }
+            else {
+                None
+            }
+        };
+
+        let is_file = metadata.as_ref().map(| m | m.file_type().is_file()).unwrap_or(false);
+
+        let hashes = {
+            if is_file {
+                [HashAlgorithm::Md5, HashAlgorithm::Sha1, HashAlgorithm::Sha256]
+                    .into_iter()
+                    .map(| algorithm | (algorithm, HashState::NotComputed))
+                    .collect()
+            }
+            else {
+                Vec::new()
+            }
+        };
+
+        let mode = if is_symlink { None } else { metadata.as_ref().and_then(PropertiesDialog::file_mode) };
+
+        let link_target = if is_symlink { std::fs::read_link(path).ok() } else { None };
+
+        let folder_size_receiver = {
+            if is_dir && !is_symlink {
+                let (sender, receiver) = std::sync::mpsc::channel();
+                let folder = path.to_path_buf();
+
+                std::thread::spawn(move || {
+                    let _ = sender.send(fs_utils::dir_size(&folder));
+                });
+
+                Some(receiver)
+            }
+            else {
+                None
+            }
+        };
+
+        PropertiesDialog {
+            path: path.to_path_buf(),
+            metadata,
+            media_type,
+            link_target,
+
+            folder_size: None,
+            folder_size_receiver,
+
+            hashes,
+
+            mode,
+            permissions_error: None
+        }
+    }
+
+    fn poll_folder_size(&mut self) {
+        if let Some(receiver) = self.folder_size_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(size) => {
+                    self.folder_size = Some(size);
+                    self.folder_size_receiver = None;
+                }
+                Err(TryRecvError::Disconnected) => self.folder_size_receiver = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+    }
+
+    fn poll_hashes(&mut self) {
+        for (_, state) in self.hashes.iter_mut() {
+            if let HashState::Computing(receiver) = state {
+                match receiver.try_recv() {
+                    Ok(Some(hash)) => *state = HashState::Done(hash),
+                    Ok(None) | Err(TryRecvError::Disconnected) => *state = HashState::Failed,
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+        }
+    }
+
+    fn start_hash(&self, ctx: &egui::Context, algorithm: HashAlgorithm) -> HashState {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let path = self.path.clone();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            let _ = sender.send(algorithm.hash_file(&path).ok());
+            ctx.request_repaint();
+        });
+
+        HashState::Computing(receiver)
+    }
+
+    // Returns false once the user closes the window.
+    pub fn show(&mut self, ctx: &egui::Context, binary_units: bool) -> bool {
+        self.poll_folder_size();
+        self.poll_hashes();
+
+        let mut open = true;
+        let mut start_hash = None;
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str()).to_string_lossy().to_string();
+
+        egui::Window::new(format!("{} Properties", name))
+            .id(egui::Id::new("properties_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, | ui | {
+                egui::Grid::new("properties_grid").num_columns(2).striped(true).show(ui, | ui | {
+                    ui.strong("Name");
+                    ui.label(&name);
+                    ui.end_row();
+
+                    ui.strong("Full path");
+                    ui.label(self.path.display().to_string());
+                    ui.end_row();
+
+                    ui.strong("Type");
+                    ui.label(self.type_description());
+                    ui.end_row();
+
+                    if let Some(target) = self.link_target.as_ref() {
+                        ui.strong("Link target");
+                        ui.label(target.display().to_string());
+                        ui.end_row();
+                    }
+
+                    ui.strong("Size");
+
+                    if self.folder_size_receiver.is_some() {
+                        ui.horizontal(| ui | {
+                            ui.spinner();
+                            ui.label("Calculating...");
+                        });
+                    }
+                    else if let Some((size, items)) = self.folder_size {
+                        ui.label(format!("{} ({} bytes), {} items", ExplorerApp::size_to_string(size as usize, binary_units), size, items));
+                    }
+                    else if let Some(metadata) = self.metadata.as_ref() {
+                        ui.label(format!("{} ({} bytes)", ExplorerApp::size_to_string(metadata.len() as usize, binary_units), metadata.len()));
+                    }
+
+                    ui.end_row();
+
+                    if let Some(metadata) = self.metadata.as_ref() {
+                        let times = [
+                            ("Created", metadata.created().ok()),
+                            ("Last accessed", metadata.accessed().ok()),
+                            ("Last modified", metadata.modified().ok())
+                        ];
+
+                        for (label, time) in times {
+                            if let Some(time) = time {
+                                ui.strong(label);
+                                ui.label(ExplorerApp::system_time_to_string(&time));
+                                ui.end_row();
+                            }
+                        }
+
+                        ui.strong("Permissions");
+                        ui.label(PropertiesDialog::permissions_description(metadata));
+                        ui.end_row();
+
+                        if let Some((owner, group)) = fs_utils::owner_and_group(metadata) {
+                            ui.strong("Owner");
+                            ui.label(owner);
+                            ui.end_row();
+
+                            ui.strong("Group");
+                            ui.label(group);
+                            ui.end_row();
+                        }
+                    }
+
+                    for (idx, (algorithm, state)) in self.hashes.iter().enumerate() {
+                        ui.strong(algorithm.name());
+
+                        match state {
+                            HashState::NotComputed => {
+                                if ui.button("Compute").clicked() {
+                                    start_hash = Some(idx);
+                                }
+                            }
+                            HashState::Computing(_) => {
+                                ui.horizontal(| ui | {
+                                    ui.spinner();
+                                    ui.label("Computing...");
+                                });
+                            }
+                            HashState::Done(hash) => {
+                                ui.horizontal(| ui | {
+                                    ui.monospace(hash);
+
+                                    if ui.button("Copy").clicked() {
+                                        ui.output_mut(| o | o.copied_text = hash.clone());
+                                    }
+                                });
+                            }
+                            HashState::Failed => {
+                                ui.horizontal(| ui | {
+                                    ui.label("Couldn't read the file");
+
+                                    if ui.button("Retry").clicked() {
+                                        start_hash = Some(idx);
+                                    }
+                                });
+                            }
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+                self.permissions_editor(ui);
+            })
+        ;
+
+        if let Some(idx) = start_hash {
+            let algorithm = self.hashes[idx].0;
+            self.hashes[idx].1 = self.start_hash(ctx, algorithm);
+        }
+
+        open
+    }
+
+    fn type_description(&self) -> String {
+        if self.link_target.is_some() {
+            "Symlink".to_string()
+        }
+        else if self.path.is_dir() {
+            "Folder".to_string()
+        }
+        else {
+            let extension = self.path.extension().unwrap_or_default().to_string_lossy();
+            file_types::type_name(&extension, self.media_type.as_deref())
+        }
+    }
+
+    #[cfg(unix)]
+    fn permissions_description(metadata: &Metadata) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = metadata.permissions().mode();
+        format!("{} ({:o})", fs_utils::mode_string(mode), mode & 0o7777)
+    }
+
+    #[cfg(unix)]
+    fn file_mode(metadata: &Metadata) -> Option<u32> {
+        use std::os::unix::fs::PermissionsExt;
+
+        Some(metadata.permissions().mode() & 0o777)
+    }
+
+    #[cfg(not(unix))]
+    fn file_mode(_metadata: &Metadata) -> Option<u32> {
+        None
+    }
+
+    // Read, write and execute checkboxes for the owner, the group and everyone else.
+    #[cfg(unix)]
+    fn permissions_editor(&mut self, ui: &mut egui::Ui) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = match self.mode.as_mut() {
+            Some(mode) => mode,
+            None => return
+        };
+
+        let current = self.metadata.as_ref().and_then(PropertiesDialog::file_mode).unwrap_or(*mode);
+
+        ui.separator();
+
+        egui::Grid::new("permission_bits").num_columns(4).show(ui, | ui | {
+            ui.label("");
+            ui.strong("Read");
+            ui.strong("Write");
+            ui.strong("Execute");
+            ui.end_row();
+
+            for (row, label) in ["Owner", "Group", "Others"].into_iter().enumerate() {
+                ui.strong(label);
+
+                for column in 0..3 {
+                    let bit = 0o400 >> (row * 3 + column);
+                    let mut set = *mode & bit != 0;
+
+                    if ui.checkbox(&mut set, "").changed() {
+                        *mode ^= bit;
+                    }
+                }
+
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(| ui | {
+            let changed = *mode != current;
+
+            if ui.add_enabled(changed, egui::Button::new("Apply")).clicked() {
+                // Setuid, setgid and sticky aren't editable here, but they shouldn't get lost either.
+                let special = self.metadata.as_ref().map(| m | m.permissions().mode() & 0o7000).unwrap_or(0);
+                let permissions = std::fs::Permissions::from_mode(special | *mode);
+
+                match std::fs::set_permissions(&self.path, permissions) {
+                    Ok(_) => {
+                        self.metadata = std::fs::symlink_metadata(&self.path).ok();
+                        self.permissions_error = None;
+                    }
+                    Err(e) => self.permissions_error = Some(format!("Couldn't change the permissions: {}", e))
+                }
+            }
+
+            if ui.add_enabled(changed, egui::Button::new("Reset")).clicked() {
+                *mode = current;
+            }
+        });
+
+        if let Some(error) = self.permissions_error.as_ref() {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn permissions_editor(&mut self, _ui: &mut egui::Ui) {}
+
+    #[cfg(not(unix))]
+    fn permissions_description(metadata: &Metadata) -> String {
+        if metadata.permissions().readonly() {
+            "Read-only".to_string()
+        }
+        else {
+            "Read/write".to_string()
+        }
+    }
+}
+
+// Streams the file through the hasher so big files don't have to fit in memory.
+fn hash_file<D: Digest>(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().iter().map(| b | format!("{:02x}", b)).collect())
+}
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+
+use eframe::egui;
+
+// Keeps a search from the root of a big drive from running forever.
+const MAX_DEPTH: usize = 16;
+const MAX_RESULTS: usize = 1000;
+
+pub struct SearchMatch {
+    pub path: PathBuf,
+    pub is_folder: bool
+}
+
+// Entries below a directory whose names contain a query, found on a background thread.
+pub struct SearchTask {
+    root: PathBuf,
+    query: String,
+    case_sensitive: bool,
+
+    matches: Vec<SearchMatch>,
+    finished: bool,
+
+    cancel: Arc<AtomicBool>,
+    receiver: Receiver<Vec<SearchMatch>>
+}
+
+impl SearchTask {
+    pub fn new(ctx: &egui::Context, root: &Path, query: &str, case_sensitive: bool) -> SearchTask {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let search_root = root.to_path_buf();
+        let search_query = if case_sensitive { query.to_string() } else { query.to_lowercase() };
+        let cancel_flag = cancel.clone();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            search(&search_root, &search_query, case_sensitive, &sender, &cancel_flag, &ctx);
+
+            // The UI notices the search is done once the channel is closed.
+            drop(sender);
+            ctx.request_repaint();
+        });
+
+        SearchTask {
+            root: root.to_path_buf(),
+            query: query.to_string(),
+            case_sensitive,
+
+            matches: Vec::new(),
+            finished: false,
+
+            cancel,
+            receiver
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Whether the search stopped early because there were too many matches.
+    pub fn is_truncated(&self) -> bool {
+        self.matches.len() >= MAX_RESULTS
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn poll(&mut self) {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(matches) => self.matches.extend(matches),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for SearchTask {
+    // A search that got replaced by a newer one shouldn't keep the disk busy.
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+// Breadth first, so matches closer to the root show up first.
+fn search(root: &Path, query: &str, case_sensitive: bool, sender: &Sender<Vec<SearchMatch>>, cancel: &AtomicBool, ctx: &egui::Context) {
+    let mut pending = VecDeque::from([(root.to_path_buf(), 0)]);
+    let mut found = 0;
+
+    while let Some((dir, depth)) = pending.pop_front() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue
+        };
+
+        let mut matches = Vec::new();
+
+        for entry in entries.flatten() {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+
+            // Links aren't followed, they could easily lead into a loop.
+            let is_folder = entry.file_type().map(| t | t.is_dir()).unwrap_or(false);
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            let name = if case_sensitive { name } else { name.to_lowercase() };
+
+            if name.contains(query) {
+                matches.push(SearchMatch {
+                    path: entry.path(),
+                    is_folder
+                });
+
+                found += 1;
+
+                if found >= MAX_RESULTS {
+                    break;
+                }
+            }
+
+            if is_folder && depth < MAX_DEPTH {
+                pending.push_back((entry.path(), depth + 1));
+            }
+        }
+
+        if !matches.is_empty() {
+            if sender.send(matches).is_err() {
+                return;
+            }
+
+            ctx.request_repaint();
+        }
+
+        if found >= MAX_RESULTS {
+            return;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+// Opens the platform's terminal emulator in `dir`.
+#[cfg(windows)]
+pub fn open(dir: &Path) -> io::Result<()> {
+    // `start` gives the shell its own console window, instead of sharing ours (or none at all).
+    Command::new("cmd").args(["/C", "start", "cmd"]).current_dir(dir).spawn().map(| _ | ())
+}
+
+#[cfg(target_os = "macos")]
+pub fn open(dir: &Path) -> io::Result<()> {
+    Command::new("open").args(["-a", "Terminal"]).arg(dir).spawn().map(| _ | ())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn open(dir: &Path) -> io::Result<()> {
+    // There's no standard way to find the user's terminal, go with the usual suspects.
+    let mut candidates: Vec<String> = std::env::var("TERMINAL").into_iter().collect();
+    candidates.extend([
+        "x-terminal-emulator", "gnome-terminal", "konsole", "xfce4-terminal", "alacritty", "kitty", "xterm"
+    ].iter().map(| name | name.to_string()));
+
+    for candidate in candidates.iter() {
+        match Command::new(candidate).current_dir(dir).spawn() {
+            Ok(_) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e)
+        }
+    }
+
+    Err(io::Error::new(io::ErrorKind::NotFound, "No terminal emulator was found"))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn open(_dir: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Opening a terminal isn't supported on this platform"))
+}
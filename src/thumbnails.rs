@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::time::SystemTime;
+
+use eframe::egui;
+
+use crate::preview;
+
+// Thumbnails are shown at the height of a row, a bit bigger keeps them sharp on high DPI screens.
+const THUMBNAIL_SIZE: u32 = 32;
+// Each one is only 4KiB, but uploaded textures aren't free either.
+const MAX_THUMBNAILS: usize = 512;
+
+// Files are told apart by their modification time too, so edited images get a new thumbnail.
+type ThumbnailKey = (PathBuf, Option<SystemTime>);
+
+struct Thumbnail {
+    // None if the file couldn't be decoded, those keep showing the regular icon.
+    texture: Option<egui::TextureHandle>,
+    last_used: u64
+}
+
+// Small previews of images for the list view, decoded on a background thread.
+#[derive(Default)]
+pub struct ThumbnailCache {
+    thumbnails: HashMap<ThumbnailKey, Thumbnail>,
+    pending: HashSet<ThumbnailKey>,
+    frame: u64,
+
+    // The worker is only started once there's something to decode.
+    sender: Option<Sender<ThumbnailKey>>,
+    receiver: Option<Receiver<(ThumbnailKey, Option<egui::ColorImage>)>>
+}
+
+impl ThumbnailCache {
+    pub fn is_supported(path: &Path) -> bool {
+        let extension = path.extension().and_then(| e | e.to_str()).unwrap_or_default().to_lowercase();
+        preview::IMAGE_EXTENSIONS.contains(&extension.as_str())
+    }
+
+    // Returns the thumbnail if it's ready, otherwise queues it up to be decoded.
+    pub fn get(&mut self, ctx: &egui::Context, path: &Path, modified: Option<SystemTime>) -> Option<egui::TextureHandle> {
+        let key = (path.to_path_buf(), modified);
+
+        if let Some(thumbnail) = self.thumbnails.get_mut(&key) {
+            thumbnail.last_used = self.frame;
+            return thumbnail.texture.clone();
+        }
+
+        if !self.pending.contains(&key) {
+            let sent = self.worker(ctx).send(key.clone()).is_ok();
+
+            if sent {
+                self.pending.insert(key);
+            }
+            else {
+                // The worker died somehow, start a new one next time.
+                self.sender = None;
+                self.receiver = None;
+            }
+        }
+
+        None
+    }
+
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        self.frame += 1;
+
+        let mut finished = false;
+
+        if let Some(receiver) = self.receiver.as_ref() {
+            loop {
+                match receiver.try_recv() {
+                    Ok((key, image)) => {
+                        self.pending.remove(&key);
+
+                        let texture = image.map(| image | {
+                            ctx.load_texture(format!("thumbnail_{}", key.0.display()), image, egui::TextureOptions::LINEAR)
+                        });
+
+                        self.thumbnails.insert(key, Thumbnail { texture, last_used: self.frame });
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if finished {
+            self.sender = None;
+            self.receiver = None;
+            self.pending.clear();
+        }
+
+        self.evict();
+    }
+
+    // Drops the least recently shown thumbnails once there are too many of them.
+    fn evict(&mut self) {
+        if self.thumbnails.len() <= MAX_THUMBNAILS {
+            return;
+        }
+
+        let mut by_use: Vec<(u64, ThumbnailKey)> = self.thumbnails.iter()
+            .map(| (key, thumbnail) | (thumbnail.last_used, key.clone()))
+            .collect()
+        ;
+
+        by_use.sort_by_key(| (last_used, _) | *last_used);
+
+        for (_, key) in by_use.into_iter().take(self.thumbnails.len() - MAX_THUMBNAILS) {
+            self.thumbnails.remove(&key);
+        }
+    }
+
+    fn worker(&mut self, ctx: &egui::Context) -> &Sender<ThumbnailKey> {
+        if self.sender.is_none() {
+            let (request_sender, request_receiver) = std::sync::mpsc::channel::<ThumbnailKey>();
+            let (result_sender, result_receiver) = std::sync::mpsc::channel();
+            let ctx = ctx.clone();
+
+            std::thread::spawn(move || {
+                // Stops once the cache is dropped along with its sender.
+                for key in request_receiver {
+                    let image = ThumbnailCache::load(&key.0);
+
+                    if result_sender.send((key, image)).is_err() {
+                        break;
+                    }
+
+                    ctx.request_repaint();
+                }
+            });
+
+            self.sender = Some(request_sender);
+            self.receiver = Some(result_receiver);
+        }
+
+        self.sender.as_ref().unwrap()
+    }
+
+    fn load(path: &Path) -> Option<egui::ColorImage> {
+        let image = image::open(path).ok()?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+
+        Some(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+    }
+}
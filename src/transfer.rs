@@ -0,0 +1,275 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+
+use crate::ClipboardMode;
+use crate::fs_utils;
+
+// Operations bigger than this get a progress dialog, smaller ones are done before anyone would notice.
+const LARGE_TRANSFER_BYTES: u64 = 64 * 1024 * 1024;
+const LARGE_TRANSFER_FILES: usize = 500;
+
+enum TransferProgress {
+    Total(u64, usize),
+    Step(u64, PathBuf),
+    Finished(TransferResult)
+}
+
+pub struct TransferResult {
+    // Original and new location of every entry that was fully copied or moved.
+    pub completed: Vec<(PathBuf, PathBuf)>,
+    pub error: Option<io::Error>,
+    pub cancelled: bool
+}
+
+// Entries being copied or moved into a folder on a background thread.
+pub struct TransferTask {
+    mode: ClipboardMode,
+    count: usize,
+    dest_dir: PathBuf,
+
+    total: Option<(u64, usize)>,
+    copied: u64,
+    current: Option<PathBuf>,
+
+    cancel: Arc<AtomicBool>,
+    receiver: Receiver<TransferProgress>
+}
+
+impl TransferTask {
+    pub fn new(sources: Vec<PathBuf>, dest_dir: &Path, mode: ClipboardMode) -> TransferTask {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let count = sources.len();
+        let target_dir = dest_dir.to_path_buf();
+        let cancel_flag = cancel.clone();
+
+        std::thread::spawn(move || {
+            let result = transfer(&sources, &target_dir, mode, &sender, &cancel_flag);
+            let _ = sender.send(TransferProgress::Finished(result));
+        });
+
+        TransferTask {
+            mode,
+            count,
+            dest_dir: dest_dir.to_path_buf(),
+
+            total: None,
+            copied: 0,
+            current: None,
+
+            cancel,
+            receiver
+        }
+    }
+
+    pub fn mode(&self) -> ClipboardMode {
+        self.mode
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn dest_dir(&self) -> &Path {
+        &self.dest_dir
+    }
+
+    pub fn progress(&self) -> f32 {
+        match self.total {
+            Some((bytes, _)) => self.copied as f32 / bytes.max(1) as f32,
+            None => 0.0
+        }
+    }
+
+    pub fn current_file(&self) -> Option<&Path> {
+        self.current.as_deref()
+    }
+
+    pub fn is_large(&self) -> bool {
+        self.total.map(| (bytes, files) | bytes >= LARGE_TRANSFER_BYTES || files >= LARGE_TRANSFER_FILES).unwrap_or(false)
+    }
+
+    // The worker stops before the next file, and removes the entry it was in the middle of.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn poll(&mut self) -> Option<TransferResult> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(TransferProgress::Total(bytes, files)) => self.total = Some((bytes, files)),
+                Ok(TransferProgress::Step(copied, current)) => {
+                    self.copied = copied;
+                    self.current = Some(current);
+                }
+                Ok(TransferProgress::Finished(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(TransferResult {
+                        completed: Vec::new(),
+                        error: Some(io::Error::other("The transfer stopped unexpectedly")),
+                        cancelled: false
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Running state of the worker, shared by the recursive copy.
+struct Transfer<'a> {
+    sender: &'a Sender<TransferProgress>,
+    cancel: &'a AtomicBool,
+    copied: u64
+}
+
+fn transfer(sources: &[PathBuf], dest_dir: &Path, mode: ClipboardMode, sender: &Sender<TransferProgress>, cancel: &AtomicBool) -> TransferResult {
+    let mut result = TransferResult {
+        completed: Vec::new(),
+        error: None,
+        cancelled: false
+    };
+
+    let (bytes, files) = sources.iter().fold((0, 0), | (bytes, files), source | {
+        let (size, items) = entry_size(source);
+        (bytes + size, files + items)
+    });
+
+    let _ = sender.send(TransferProgress::Total(bytes, files));
+
+    let mut state = Transfer {
+        sender,
+        cancel,
+        copied: 0
+    };
+
+    for source in sources {
+        if cancel.load(Ordering::Relaxed) {
+            result.cancelled = true;
+            break;
+        }
+
+        // Moving an entry to where it already is doesn't do anything.
+        if mode == ClipboardMode::Cut && source.parent() == Some(dest_dir) {
+            continue;
+        }
+
+        match transfer_entry(source, dest_dir, mode, &mut state) {
+            Ok(Some(target)) => result.completed.push((source.clone(), target)),
+            Ok(None) => {
+                result.cancelled = true;
+                break;
+            }
+            Err(e) => {
+                result.error = Some(io::Error::new(e.kind(), format!("{}: {}", source.display(), e)));
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+// Returns the new location of the entry, or None if the transfer was cancelled midway.
+fn transfer_entry(source: &Path, dest_dir: &Path, mode: ClipboardMode, state: &mut Transfer) -> io::Result<Option<PathBuf>> {
+    if source.is_dir() && dest_dir.starts_with(source) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Cannot copy a folder into itself"));
+    }
+
+    // Copies get a free name next to the original, moves keep theirs and don't replace anything.
+    let target = {
+        if mode == ClipboardMode::Cut {
+            let target = dest_dir.join(source.file_name().unwrap_or_default());
+
+            if target.exists() {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", target.display())));
+            }
+
+            target
+        }
+        else {
+            fs_utils::unique_path(dest_dir, source)
+        }
+    };
+
+    if mode == ClipboardMode::Cut {
+        match std::fs::rename(source, &target) {
+            Ok(_) => {
+                state.copied += entry_size(&target).0;
+                let _ = state.sender.send(TransferProgress::Step(state.copied, target.clone()));
+
+                return Ok(Some(target));
+            }
+            Err(e) if fs_utils::is_cross_device(&e) => {}
+            Err(e) => return Err(e)
+        }
+    }
+
+    match copy_with_progress(source, &target, state) {
+        Ok(true) => {}
+        Ok(false) => {
+            // Don't leave half a copy behind.
+            let _ = fs_utils::remove_entry(&target);
+            return Ok(None);
+        }
+        Err(e) => {
+            let _ = fs_utils::remove_entry(&target);
+            return Err(e);
+        }
+    }
+
+    if mode == ClipboardMode::Cut {
+        fs_utils::remove_entry(source)?;
+    }
+
+    Ok(Some(target))
+}
+
+// Same as fs_utils::copy_recursive, reporting progress and checking for cancellation after every file.
+// Returns false if the copy was cancelled.
+fn copy_with_progress(source: &Path, target: &Path, state: &mut Transfer) -> io::Result<bool> {
+    if state.cancel.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+
+    let metadata = std::fs::symlink_metadata(source)?;
+
+    if metadata.file_type().is_symlink() {
+        fs_utils::copy_symlink(source, target)?;
+    }
+    else if metadata.is_dir() {
+        std::fs::create_dir(target)?;
+
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+
+            if !copy_with_progress(&entry.path(), &target.join(entry.file_name()), state)? {
+                return Ok(false);
+            }
+        }
+    }
+    else {
+        let _ = state.sender.send(TransferProgress::Step(state.copied, source.to_path_buf()));
+
+        std::fs::copy(source, target)?;
+        state.copied += metadata.len();
+    }
+
+    Ok(true)
+}
+
+fn entry_size(path: &Path) -> (u64, usize) {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs_utils::dir_size(path),
+        Ok(metadata) => (metadata.len(), 1),
+        Err(_) => (0, 0)
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+// A folder in the tree. Its subfolders are only read the first time it's expanded.
+#[derive(Clone)]
+struct TreeNode {
+    path: PathBuf,
+    name: String,
+    children: Option<Vec<TreeNode>>
+}
+
+impl TreeNode {
+    fn new(path: PathBuf) -> TreeNode {
+        let name = path.file_name()
+            .map(| name | name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string())
+        ;
+
+        TreeNode {
+            path,
+            name,
+            children: None
+        }
+    }
+
+    fn load_children(&mut self) {
+        let mut children: Vec<TreeNode> = std::fs::read_dir(&self.path)
+            .map(| entries | {
+                entries.flatten()
+                    // Links to folders count too, is_dir follows them.
+                    .filter(| entry | entry.path().is_dir())
+                    .map(| entry | TreeNode::new(entry.path()))
+                    .collect()
+            })
+            .unwrap_or_default()
+        ;
+
+        children.sort_by_key(| child | child.name.to_lowercase());
+        self.children = Some(children);
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui, expanded: &mut BTreeSet<PathBuf>, state: &mut ShowState) {
+        let is_expanded = expanded.contains(&self.path);
+
+        if is_expanded && self.children.is_none() {
+            self.load_children();
+        }
+
+        ui.horizontal(| ui | {
+            let has_children = self.children.as_ref().map(| children | !children.is_empty()).unwrap_or(true);
+
+            if has_children {
+                let arrow = if is_expanded { "⏷" } else { "⏵" };
+
+                if ui.add(egui::Button::new(arrow).frame(false)).clicked() {
+                    if is_expanded {
+                        expanded.remove(&self.path);
+                        // Read again when it's reopened, in case something changed meanwhile.
+                        self.children = None;
+                    }
+                    else {
+                        expanded.insert(self.path.clone());
+                    }
+                }
+            }
+            else {
+                ui.add_space(ui.spacing().interact_size.y);
+            }
+
+            let is_current = self.path == state.current_path;
+            let response = ui.selectable_label(is_current, format!("🗀 {}", self.name))
+                .on_hover_text(self.path.display().to_string())
+            ;
+
+            if is_current && state.scroll_to_current {
+                response.scroll_to_me(Some(egui::Align::Center));
+            }
+
+            if response.clicked() {
+                state.clicked = Some(self.path.clone());
+            }
+        });
+
+        if is_expanded {
+            if let Some(children) = self.children.as_mut() {
+                ui.indent(&self.path, | ui | {
+                    for child in children.iter_mut() {
+                        child.show(ui, expanded, state);
+                    }
+                });
+            }
+        }
+    }
+}
+
+struct ShowState {
+    current_path: PathBuf,
+    scroll_to_current: bool,
+    clicked: Option<PathBuf>
+}
+
+// The folder tree of the sidebar. Only which folders are expanded is saved,
+// the folders themselves are read again when the app starts.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DirTree {
+    expanded: BTreeSet<PathBuf>,
+
+    #[serde(skip)]
+    roots: Vec<TreeNode>,
+    // The directory the tree was last expanded to.
+    #[serde(skip)]
+    synced_path: Option<PathBuf>
+}
+
+impl DirTree {
+    // Returns the folder that was clicked, if any.
+    pub fn show(&mut self, ui: &mut egui::Ui, current_path: &Path) -> Option<PathBuf> {
+        if self.roots.is_empty() {
+            self.roots = roots().into_iter().map(TreeNode::new).collect();
+        }
+
+        // Opens up every folder above the current one when navigating somewhere else.
+        let scroll_to_current = self.synced_path.as_deref() != Some(current_path);
+
+        if scroll_to_current {
+            for ancestor in current_path.ancestors().skip(1) {
+                self.expanded.insert(ancestor.to_path_buf());
+            }
+
+            self.synced_path = Some(current_path.to_path_buf());
+        }
+
+        let mut state = ShowState {
+            current_path: current_path.to_path_buf(),
+            scroll_to_current,
+            clicked: None
+        };
+
+        egui::ScrollArea::both().auto_shrink([false, false]).show(ui, | ui | {
+            for root in self.roots.iter_mut() {
+                root.show(ui, &mut self.expanded, &mut state);
+            }
+        });
+
+        state.clicked
+    }
+}
+
+#[cfg(windows)]
+fn roots() -> Vec<PathBuf> {
+    // A: and B: are floppy drives, probing them can take ages.
+    ('C'..='Z')
+        .map(| letter | PathBuf::from(format!("{}:\\", letter)))
+        .filter(| root | root.exists())
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn roots() -> Vec<PathBuf> {
+    vec![PathBuf::from("/")]
+}
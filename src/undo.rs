@@ -0,0 +1,72 @@
+use std::io;
+use std::path::PathBuf;
+
+use crate::fs_utils;
+
+// A file operation, with enough information to reverse it.
+pub enum FileOp {
+    Rename { from: PathBuf, to: PathBuf },
+    // Original and new location of every entry that was moved.
+    Move(Vec<(PathBuf, PathBuf)>),
+    // Entries that were created by copying, pasting or extracting.
+    Create(Vec<PathBuf>),
+    // Original location of every entry that was sent to the trash.
+    Trash(Vec<PathBuf>)
+}
+
+impl FileOp {
+    pub fn undo(self) -> io::Result<()> {
+        match self {
+            FileOp::Rename { from, to } => {
+                if from.exists() {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", from.display())));
+                }
+
+                std::fs::rename(to, from)
+            }
+            FileOp::Move(entries) => {
+                for (from, to) in entries.into_iter().rev() {
+                    fs_utils::move_entry(&to, &from)?;
+                }
+
+                Ok(())
+            }
+            FileOp::Create(paths) => {
+                trash::delete_all(&paths).map_err(trash_error)
+            }
+            FileOp::Trash(paths) => restore(paths)
+        }
+    }
+}
+
+// Whether entries sent to the trash can be brought back on this platform.
+pub fn can_restore_trash() -> bool {
+    cfg!(any(target_os = "windows", all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))))
+}
+
+fn trash_error(error: trash::Error) -> io::Error {
+    io::Error::other(error.to_string())
+}
+
+#[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))))]
+fn restore(paths: Vec<PathBuf>) -> io::Result<()> {
+    let mut items = trash::os_limited::list().map_err(trash_error)?;
+    let mut restored = Vec::new();
+
+    // The same path could have been trashed more than once, the latest one is ours.
+    items.sort_by_key(| item | std::cmp::Reverse(item.time_deleted));
+
+    for path in paths.iter() {
+        match items.iter().position(| item | item.original_path() == *path) {
+            Some(position) => restored.push(items.remove(position)),
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} is no longer in the trash", path.display())))
+        }
+    }
+
+    trash::os_limited::restore_all(restored).map_err(trash_error)
+}
+
+#[cfg(not(any(target_os = "windows", all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))))]
+fn restore(_paths: Vec<PathBuf>) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Restoring from the trash isn't supported on this platform"))
+}
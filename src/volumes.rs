@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+// A drive or mounted filesystem that can be browsed.
+pub struct Volume {
+    pub root: PathBuf,
+    pub label: String,
+    // Available and total bytes.
+    pub space: Option<(u64, u64)>
+}
+
+impl Volume {
+    fn new(root: PathBuf, label: String) -> Volume {
+        let space = fs2::available_space(&root).ok().zip(fs2::total_space(&root).ok());
+
+        Volume {
+            root,
+            label,
+            space
+        }
+    }
+}
+
+// Can block for a while on slow or disconnected network mounts, call it off the UI thread.
+pub fn list() -> Vec<Volume> {
+    mount_points().into_iter()
+        .map(| (root, label) | Volume::new(root, label))
+        .collect()
+}
+
+#[cfg(windows)]
+fn mount_points() -> Vec<(PathBuf, String)> {
+    // A: and B: are floppy drives, probing them can take ages.
+    ('C'..='Z')
+        .map(| letter | (PathBuf::from(format!("{}:\\", letter)), format!("{}:", letter)))
+        .filter(| (root, _) | root.exists())
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn mount_points() -> Vec<(PathBuf, String)> {
+    let mut volumes = vec![(PathBuf::from("/"), "Macintosh HD".to_string())];
+
+    if let Ok(entries) = std::fs::read_dir("/Volumes") {
+        for entry in entries.flatten() {
+            // The boot volume shows up in here as a link to /.
+            if !entry.path().is_symlink() {
+                volumes.push((entry.path(), entry.file_name().to_string_lossy().to_string()));
+            }
+        }
+    }
+
+    volumes
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_points() -> Vec<(PathBuf, String)> {
+    let mounts = std::fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let mut volumes = Vec::new();
+
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+
+        let (device, mount_point) = match (fields.next(), fields.next()) {
+            (Some(device), Some(mount_point)) => (device, unescape_mount_path(mount_point)),
+            _ => continue
+        };
+
+        // Everything that isn't backed by a device is proc, sysfs, tmpfs, cgroups and friends.
+        // Snaps are mounted as loop devices, one per package.
+        if !device.starts_with("/dev/") || device.starts_with("/dev/loop") || mount_point.starts_with("/boot") {
+            continue;
+        }
+
+        if volumes.iter().any(| (root, _) | *root == mount_point) {
+            continue;
+        }
+
+        volumes.push((mount_point.clone(), volume_label(&mount_point)));
+    }
+
+    if volumes.is_empty() {
+        volumes.push((PathBuf::from("/"), volume_label(std::path::Path::new("/"))));
+    }
+
+    volumes
+}
+
+#[cfg(not(any(unix, windows)))]
+fn mount_points() -> Vec<(PathBuf, String)> {
+    Vec::new()
+}
+
+// Spaces and other special characters are written as octal escapes, like "\040".
+#[cfg(all(unix, not(target_os = "macos")))]
+fn unescape_mount_path(path: &str) -> PathBuf {
+    let mut unescaped = String::new();
+    let mut chars = path.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => unescaped.push(byte as char),
+                Err(_) => {
+                    unescaped.push(c);
+                    unescaped.push_str(&code);
+                }
+            }
+        }
+        else {
+            unescaped.push(c);
+        }
+    }
+
+    PathBuf::from(unescaped)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn volume_label(root: &std::path::Path) -> String {
+    match root.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => "File System".to_string()
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+// Changes usually come in bursts (a download, an extraction...), wait for things to settle.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Watches a single directory for entries being added, removed or modified by other programs.
+pub struct DirWatcher {
+    path: PathBuf,
+    // Watching isn't possible everywhere (some network shares, too many watches already...),
+    // those directories just need a manual refresh. Dropping the watcher stops it.
+    _watcher: Option<RecommendedWatcher>,
+    receiver: Receiver<()>,
+    changed_at: Option<Instant>
+}
+
+impl DirWatcher {
+    pub fn new(ctx: &egui::Context, path: &Path) -> DirWatcher {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+
+        let watcher = notify::recommended_watcher(move | event: notify::Result<notify::Event> | {
+            // Reading the directory and sniffing file types shows up as access events,
+            // refreshing on those would never stop.
+            if let Ok(event) = event {
+                if !event.kind.is_access() && sender.send(()).is_ok() {
+                    ctx.request_repaint_after(DEBOUNCE);
+                }
+            }
+        });
+
+        let watcher = watcher.and_then(| mut watcher | {
+            watcher.watch(path, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+
+        DirWatcher {
+            path: path.to_path_buf(),
+            _watcher: watcher.ok(),
+            receiver,
+            changed_at: None
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Returns true once changes were seen and no new ones arrived for a little while.
+    pub fn poll(&mut self) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(_) => self.changed_at = Some(Instant::now()),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break
+            }
+        }
+
+        match self.changed_at {
+            Some(changed_at) if changed_at.elapsed() >= DEBOUNCE => {
+                self.changed_at = None;
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.changed_at.is_some()
+    }
+}